@@ -2,3 +2,6 @@
 set -euxo pipefail # https://vaneyckt.io/posts/safer_bash_scripts_with_set_euxo_pipefail/
 
 cargo test --all
+
+cargo test --features actix-web --lib server
+cargo test --features warp --lib server
@@ -2,6 +2,7 @@ extern crate proc_macro;
 use proc_macro::TokenStream;
 use syn::{parse_macro_input, DeriveInput};
 
+mod route;
 mod switch;
 
 /// Implements the `Switch` trait based on attributes present on the struct or enum variants.
@@ -21,11 +22,45 @@ mod switch;
 /// and if the associated variant is defined as `Route{id: usize}`, then the string that was captured will be
 /// transformed into a `usize`.
 /// If the conversion fails, then the match won't succeed and the next variant will be tried instead.
+/// Captured values are percent-decoded before they are converted,
+/// and percent-encoded when building a route, so a `String` field can hold characters like `/` or `?`.
 ///
 /// There are also `{*:field_name}` and `{3:field_name}` types of capture sections that will capture
 /// _everything_, and the next 3 path sections respectively.
+/// Because `{*:field_name}` is usually used to delegate to a nested `Switch`, which decodes its own
+/// captures, what it captures is neither decoded nor encoded.
 /// `{1:field_name}` is the same as `{field_name}`.
 ///
+/// A single section capture can be constrained, so it only matches if the section satisfies the
+/// constraint, otherwise the next variant is tried.
+/// `{id:u32}` will only match sections that can be parsed as a `u32`,
+/// and `{slug:[a-z-]+}` will only match sections made up of lowercase letters and dashes.
+/// Constraints can be any primitive numeric type, `bool` or `char`, or a character class
+/// followed by `+` (at least one character) or `*` (possibly empty).
+///
+/// Query parameters, like `?q={query}&page={page}`, can appear in any order in the route,
+/// and any parameters that aren't specified are ignored.
+/// A parameter can be made optional by ending its capture with a `?`, like `?page={page?}`.
+/// Optional parameters must be stored in an `Option` field, which will be `None` if the
+/// parameter is absent, or in a field marked with `#[default]`, which will be populated with
+/// `Switch::key_not_available` or else `Default::default()`.
+/// When building a route, parameters stored in `Option` fields are left out if they are `None`.
+/// Every value of a repeated key, like in `?tag=a&tag=b`, can be captured into a `Vec` field
+/// with `?tag={*:tags}`, and a `tag=` pair is written for each element when building a route.
+///
+/// A set of alternative literals can be matched by separating them with `|` within parenthesis.
+/// `#[to = "/(users|members)/{id}"]` will match both "/users/42" and "/members/42".
+/// The first alternative is used when building a route.
+/// Literal parenthesis can be matched by escaping them as `((` and `))`.
+///
+/// Parts of a path can be made optional by wrapping them in square brackets.
+/// `#[to = "/posts[/page/{page}]"]` will match both "/posts" and "/posts/page/3".
+/// Captures within an optional group must be stored in an `Option` field,
+/// which will be `None` if the group isn't present in the route.
+/// When building a route, the group is left out if any of its fields are `None`.
+/// Optional groups can't be nested, and must start with a `/` or a literal.
+/// Literal brackets can be matched by escaping them as `[[` and `]]`.
+///
 /// Tuple-structs and Tuple-enum-variants are also supported.
 /// If you don't want to specify keys that don't correspond to any specific field,
 /// `{}`, `{*}`, and `{4}` also denote valid capture sections when used on structs and variants without named fields.
@@ -42,6 +77,33 @@ mod switch;
 /// any characters are left after matching the route matcher string, the match should fail.
 /// This means that `[to = "/!"]` will match "/" and _only_ "/".
 ///
+/// A warning is produced for any variant that can never be matched,
+/// because an earlier variant matches every route that it would,
+/// like `#[to = "/user/new"]` following `#[to = "/user/{name}"]` where `name` is a `String`.
+/// Because a derive can only produce warnings by using deprecated items,
+/// the warning can be silenced with `#[allow(deprecated)]` on the variant or the enum.
+///
+/// Instead of trying variants in the order they are declared,
+/// `#[switch(order = "specificity")]` on the enum tries the most specific variants first.
+/// Variants that require longer literals are more specific, then those with fewer `{*}` captures,
+/// then those with fewer captures of any other kind, and then those that end with `!`.
+/// Optional groups and optional query parameters aren't counted.
+/// Equally specific variants are tried in the order they are declared.
+///
+/// Literals are matched without regard to case by default,
+/// so `#[to = "/about"]` also matches "/About".
+/// `#[switch(case_sensitive)]` on the enum or struct makes its matchers case sensitive,
+/// and `#[switch(case_sensitive)]` or `#[switch(case_insensitive)]` on a variant overrides the
+/// enum for that variant.
+/// Routes are always built with literals in the case they were declared.
+///
+/// A trailing slash must be present in a route if and only if the matcher ends with one.
+/// `#[switch(trailing_slash = "ignore")]` lets routes match whether or not they end with a slash,
+/// and `#[switch(trailing_slash = "redirect")]` additionally makes the `Router` replace the route
+/// with the one built from the switch, so its trailing slash agrees with the matcher.
+/// `#[switch(trailing_slash = "strict")]` restores the default.
+/// Like case sensitivity, the policy can be set on the enum or struct and overridden on a variant.
+///
 /// -----
 /// There are other attributes as well.
 /// `#[rest]`, `#[rest="field_name"]` and `#[end]` attributes exist as well.
@@ -77,7 +139,7 @@ mod switch;
 /// }
 /// ```
 /// Check out the examples directory in the repository to see some more usages of the routing syntax.
-#[proc_macro_derive(Switch, attributes(to, rest, end))]
+#[proc_macro_derive(Switch, attributes(to, rest, end, default, switch))]
 pub fn switch(tokens: TokenStream) -> TokenStream {
     let input: DeriveInput = parse_macro_input!(tokens as DeriveInput);
 
@@ -86,6 +148,46 @@ pub fn switch(tokens: TokenStream) -> TokenStream {
         .into()
 }
 
+/// Builds a `Route<()>` from a route matcher string, with a value for every capture.
+///
+/// The matcher string is parsed at compile time, the same way as in a `#[to = ""]` attribute,
+/// and every named capture must be given a value, like `id = user.id`.
+/// Values are written with `Display` and percent-encoded, like fields of a derived `Switch`.
+/// Values captured with `{*:name}` in the path must implement `Switch` instead,
+/// so that a route of a nested `Switch` can be given, and they aren't encoded.
+/// Optional groups and optional query parameters are written when their values are `Some`,
+/// and repeated query keys are written for every element of a `Vec`.
+///
+/// Literals are written as they are, and `!` doesn't add anything to the route.
+///
+/// # Example
+/// ```
+/// use yew_router::route;
+///
+/// let id = 5;
+/// let route = route!("/users/{id}/posts?q={query}", id = id, query = "a&b");
+/// assert_eq!(route.route, "/users/5/posts?q=a%26b");
+/// ```
+///
+/// Every value must be captured by the route,
+/// ```compile_fail
+/// # use yew_router::route;
+/// let route = route!("/users/{id}", id = 5, name = "name");
+/// ```
+/// and every capture must be given a value.
+/// ```compile_fail
+/// # use yew_router::route;
+/// let route = route!("/users/{id}/posts/{post}", id = 5);
+/// ```
+#[proc_macro]
+pub fn route(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as crate::route::RouteInput);
+
+    crate::route::route_impl(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
 #[proc_macro_attribute]
 pub fn to(_: TokenStream, _: TokenStream) -> TokenStream {
     TokenStream::new()
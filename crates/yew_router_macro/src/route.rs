@@ -0,0 +1,223 @@
+use crate::switch::{
+    attribute::AttrToken,
+    shadow::{ShadowCaptureVariant, ShadowMatcherToken, ShadowQueryValue},
+    write_for_token, FieldType,
+};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    Ident, LitStr, Token,
+};
+use yew_router_route_parser::FieldNamingScheme;
+
+/// The input of `route!`: a matcher string followed by `name = value` pairs.
+pub struct RouteInput {
+    pattern: LitStr,
+    values: Vec<(Ident, TokenStream)>,
+}
+
+impl Parse for RouteInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let pattern: LitStr = input.parse()?;
+        let mut values = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name: Ident = input.parse()?;
+            let eq: Token![=] = input.parse()?;
+            // Values are taken up to the next comma, so they don't have to be parsed as
+            // expressions.
+            let mut value = TokenStream::new();
+            while !input.is_empty() && !input.peek(Token![,]) {
+                value.extend(std::iter::once(input.parse::<TokenTree>()?));
+            }
+            if value.is_empty() {
+                return Err(syn::Error::new(eq.span, "Expected a value after `=`."));
+            }
+            values.push((name, value));
+        }
+        Ok(RouteInput { pattern, values })
+    }
+}
+
+pub fn route_impl(input: RouteInput) -> syn::Result<TokenStream> {
+    let RouteInput { pattern, values } = input;
+    let mut tokens = AttrToken::To(pattern.clone())
+        .into_shadow_matcher_tokens(0, FieldNamingScheme::Named)?;
+    // Optional query parameters are written if their value is `Some`.
+    tokens.iter_mut().for_each(omit_optional_params_if_none);
+
+    // The captures are renamed, so that their bindings don't collide with the ones used to
+    // write the route.
+    let mut captures = Vec::new();
+    tokens
+        .iter_mut()
+        .for_each(|token| collect_captures(token, false, &mut captures));
+    let capture_names: Vec<String> = captures.iter().map(|capture| capture.name.clone()).collect();
+
+    for (index, (name, _)) in values.iter().enumerate() {
+        if values[..index].iter().any(|(earlier, _)| earlier == name) {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("A value was already given for `{}`.", name),
+            ));
+        }
+        if !capture_names.contains(&name.to_string()) {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("`{}` isn't captured by the route.", name),
+            ));
+        }
+    }
+    let missing: Vec<&String> = capture_names
+        .iter()
+        .filter(|capture| !values.iter().any(|(name, _)| name == capture.as_str()))
+        .collect();
+    if let Some(first) = missing.first() {
+        let missing: Vec<String> = missing.iter().map(|name| format!("`{}`", name)).collect();
+        return Err(syn::Error::new(
+            pattern.span(),
+            format!(
+                "No value was given for {}, which the route captures. Give values like `{} = ...`.",
+                missing.join(", "),
+                first
+            ),
+        ));
+    }
+
+    // The values are evaluated together, so they can't refer to each other's bindings.
+    let bindings = values.iter().map(|(name, _)| binding_ident(&name.to_string()));
+    let values = values.iter().map(|(name, value)| {
+        let capture = captures
+            .iter()
+            .find(|capture| name == capture.name.as_str())
+            .expect("Every value was checked to have a capture");
+        capture.kind.convert(value.clone())
+    });
+    let writers = tokens
+        .iter()
+        .map(|token| write_for_token(token, FieldType::Named));
+    Ok(quote! {
+        {
+            let (#(#bindings,)*) = (#(#values,)*);
+            let mut __route = ::std::string::String::new();
+            {
+                use ::std::fmt::Write as _;
+                use ::yew_router::Switch as _;
+                let buf = &mut __route;
+                #[allow(unused_mut)]
+                let mut state: ::std::option::Option<()> = ::std::option::Option::None;
+                #(#writers)*
+                let _ = state;
+            }
+            ::yew_router::route::Route::new_no_state(__route)
+        }
+    })
+}
+
+fn omit_optional_params_if_none(token: &mut ShadowMatcherToken) {
+    match token {
+        ShadowMatcherToken::Query { params, .. } => params
+            .iter_mut()
+            .for_each(|param| param.omit_if_none = param.optional),
+        ShadowMatcherToken::Optional(group) => {
+            group.iter_mut().for_each(omit_optional_params_if_none)
+        }
+        _ => {}
+    }
+}
+
+/// A capture of the route, under the name it was given in the matcher string.
+struct Capture {
+    name: String,
+    kind: ValueKind,
+}
+
+/// How the value of a capture is written.
+enum ValueKind {
+    /// Written with `Display`, and percent-encoded.
+    Display,
+    /// Written with `Switch`, so that a route of a nested `Switch` can be given for `{*:name}`.
+    Route,
+    /// Written with `Display` for every element, like `Vec<T>` for `?key={*:name}`.
+    Repeated,
+    /// Written if it is `Some`, like `Option<T>` in an optional group or for `?key={name?}`.
+    Optional(Box<ValueKind>),
+}
+
+impl ValueKind {
+    /// Converts a value into one that implements `Switch`, so that it can be written
+    /// like a field of a derived `Switch`.
+    fn convert(&self, value: TokenStream) -> TokenStream {
+        match self {
+            ValueKind::Display => quote! {::std::string::ToString::to_string(&(#value))},
+            ValueKind::Route => value,
+            ValueKind::Repeated => quote! {
+                ::std::iter::Iterator::collect::<::std::vec::Vec<::std::string::String>>(
+                    ::std::iter::Iterator::map(
+                        ::std::iter::IntoIterator::into_iter(#value),
+                        |__value| ::std::string::ToString::to_string(&__value),
+                    )
+                )
+            },
+            ValueKind::Optional(kind) => {
+                let converted = kind.convert(quote! {__value});
+                quote! {::std::option::Option::map(#value, |__value| #converted)}
+            }
+        }
+    }
+}
+
+/// The name that the value of a capture is bound to.
+fn binding_ident(name: &str) -> Ident {
+    Ident::new(&format!("__route_value_{}", name), Span::call_site())
+}
+
+/// Collects the captures in the order they appear, renaming them to their bindings.
+fn collect_captures(token: &mut ShadowMatcherToken, in_group: bool, captures: &mut Vec<Capture>) {
+    let mut collect = |capture: &mut ShadowCaptureVariant, kind: ValueKind| {
+        if let Some(name) = capture.name_mut() {
+            let kind = if in_group {
+                ValueKind::Optional(Box::new(kind))
+            } else {
+                kind
+            };
+            captures.push(Capture {
+                name: name.clone(),
+                kind,
+            });
+            *name = binding_ident(name).to_string();
+        }
+    };
+    match token {
+        ShadowMatcherToken::Capture(capture) => {
+            let kind = if capture.is_many() {
+                ValueKind::Route
+            } else {
+                ValueKind::Display
+            };
+            collect(capture, kind)
+        }
+        ShadowMatcherToken::Query { params, .. } => {
+            for param in params {
+                if let ShadowQueryValue::Capture(capture) = &mut param.value {
+                    let kind = if capture.is_many() {
+                        ValueKind::Repeated
+                    } else if param.optional {
+                        ValueKind::Optional(Box::new(ValueKind::Display))
+                    } else {
+                        ValueKind::Display
+                    };
+                    collect(capture, kind);
+                }
+            }
+        }
+        ShadowMatcherToken::Optional(group) => group
+            .iter_mut()
+            .for_each(|token| collect_captures(token, true, captures)),
+        ShadowMatcherToken::Exact(_) | ShadowMatcherToken::OneOf(_) | ShadowMatcherToken::End => {}
+    }
+}
@@ -1,23 +1,40 @@
-use crate::switch::shadow::{ShadowCaptureVariant, ShadowMatcherToken};
+use crate::switch::shadow::{ShadowCaptureVariant, ShadowMatcherToken, ShadowQueryValue};
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens};
-use syn::{Data, DeriveInput, Fields, Ident, Variant};
+use std::cmp::Reverse;
+use syn::{
+    Attribute, Data, DeriveInput, Field, Fields, GenericArgument, Ident, PathArguments, Type,
+    Variant,
+};
 
-mod attribute;
+pub(crate) mod attribute;
 mod enum_impl;
-mod shadow;
+mod item_methods;
+mod reachability;
+pub(crate) mod shadow;
+mod specificity;
 mod struct_impl;
 mod switch_impl;
 
-use self::{attribute::AttrToken, switch_impl::SwitchImpl};
+use self::{
+    attribute::{AttrToken, SwitchOptions, TrailingSlash, VariantOrder},
+    item_methods::ItemMethods,
+    specificity::Specificity,
+    switch_impl::SwitchImpl,
+};
 use crate::switch::{enum_impl::EnumInner, struct_impl::StructInner};
 use yew_router_route_parser::FieldNamingScheme;
 
 /// Holds data that is required to derive Switch for a struct or a single enum variant.
 pub struct SwitchItem {
     pub matcher: Vec<ShadowMatcherToken>,
+    /// The matcher as it was written in the attributes.
+    pub matcher_string: String,
     pub ident: Ident,
     pub fields: Fields,
+    /// Literals are matched with regard to their case.
+    pub case_sensitive: bool,
+    pub trailing_slash: TrailingSlash,
 }
 
 pub fn switch_impl(input: DeriveInput) -> syn::Result<TokenStream> {
@@ -26,23 +43,9 @@ pub fn switch_impl(input: DeriveInput) -> syn::Result<TokenStream> {
 
     Ok(match input.data {
         Data::Struct(ds) => {
-            let field_naming_scheme = match ds.fields {
-                Fields::Unnamed(_) => FieldNamingScheme::Unnamed,
-                Fields::Unit => FieldNamingScheme::Unit,
-                Fields::Named(_) => FieldNamingScheme::Named,
-            };
-            let matcher = AttrToken::convert_attributes_to_tokens(input.attrs)?
-                .into_iter()
-                .enumerate()
-                .map(|(index, at)| at.into_shadow_matcher_tokens(index, field_naming_scheme))
-                .flatten()
-                .collect::<Vec<_>>();
-
-            let item = SwitchItem {
-                matcher,
-                ident: ident.clone(), // TODO make SwitchItem take references instead.
-                fields: ds.fields,
-            };
+            let options = SwitchOptions::default().with_item_attributes(&input.attrs)?;
+            // TODO make SwitchItem take references instead.
+            let item = switch_item(ident.clone(), input.attrs, ds.fields, options)?;
 
             SwitchImpl {
                 target_ident: &ident,
@@ -53,36 +56,40 @@ pub fn switch_impl(input: DeriveInput) -> syn::Result<TokenStream> {
                         switch_item: &item,
                         item: &Ident::new("self", Span::call_site()),
                     },
+                    item_methods: ItemMethods {
+                        switch_items: std::slice::from_ref(&item),
+                        enum_ident: None,
+                    },
                 },
             }
             .to_token_stream()
         }
         Data::Enum(de) => {
-            let switch_variants = de
+            let options = SwitchOptions::from_attributes(&input.attrs)?;
+            let enum_lints = reachability::lint_attributes(&input.attrs);
+            let mut variants = de
                 .variants
                 .into_iter()
                 .map(|variant: Variant| {
-                    let field_type = match variant.fields {
-                        Fields::Unnamed(_) => yew_router_route_parser::FieldNamingScheme::Unnamed,
-                        Fields::Unit => FieldNamingScheme::Unit,
-                        Fields::Named(_) => yew_router_route_parser::FieldNamingScheme::Named,
-                    };
-                    let matcher = AttrToken::convert_attributes_to_tokens(variant.attrs)?
-                        .into_iter()
-                        .enumerate()
-                        .map(|(index, at)| at.into_shadow_matcher_tokens(index, field_type))
-                        .flatten()
-                        .collect::<Vec<_>>();
-                    Ok(SwitchItem {
-                        matcher,
-                        ident: variant.ident,
-                        fields: variant.fields,
-                    })
+                    let lints = reachability::lint_attributes(&variant.attrs);
+                    let variant_options = options.with_item_attributes(&variant.attrs)?;
+                    let item =
+                        switch_item(variant.ident, variant.attrs, variant.fields, variant_options)?;
+                    Ok((item, lints))
                 })
                 .collect::<syn::Result<Vec<_>>>()?;
+            if options.order == VariantOrder::Specificity {
+                // The sort is stable, so equally specific variants keep their declared order.
+                variants.sort_by_key(|(item, _)| Reverse(Specificity::of(item)));
+            }
+            let (switch_variants, variant_lints): (Vec<_>, Vec<_>) = variants.into_iter().unzip();
+            let warnings = reachability::shadowed_variant_warnings(
+                &switch_variants,
+                &enum_lints,
+                &variant_lints,
+            );
 
-
-            SwitchImpl {
+            let switch_impl = SwitchImpl {
                 target_ident: &ident,
                 generics: &generics,
                 inner: EnumInner {
@@ -95,14 +102,63 @@ pub fn switch_impl(input: DeriveInput) -> syn::Result<TokenStream> {
                         enum_ident: &ident,
                         match_item: &Ident::new("self", Span::call_site()),
                     },
+                    item_methods: ItemMethods {
+                        switch_items: &switch_variants,
+                        enum_ident: Some(&ident),
+                    },
                 },
+            };
+            quote! {
+                #switch_impl
+                #warnings
             }
-            .to_token_stream()
         }
-        Data::Union(_du) => panic!("Deriving FromCaptures not supported for Unions."),
+        Data::Union(du) => {
+            return Err(syn::Error::new_spanned(
+                du.union_token,
+                "Deriving Switch is not supported for unions.",
+            ))
+        }
     })
 }
 
+/// Builds the item for a struct or an enum variant from its `#[to]`, `#[rest]` and `#[end]`
+/// attributes.
+///
+/// The options should already include those set on the item itself.
+fn switch_item(
+    ident: Ident,
+    attrs: Vec<Attribute>,
+    fields: Fields,
+    options: SwitchOptions,
+) -> syn::Result<SwitchItem> {
+    let field_naming_scheme = match fields {
+        Fields::Unnamed(_) => FieldNamingScheme::Unnamed,
+        Fields::Unit => FieldNamingScheme::Unit,
+        Fields::Named(_) => FieldNamingScheme::Named,
+    };
+    let attr_tokens = AttrToken::convert_attributes_to_tokens(attrs)?;
+    let matcher_string = AttrToken::matcher_string(&attr_tokens);
+    let matcher = attr_tokens
+        .into_iter()
+        .enumerate()
+        .map(|(index, at)| at.into_shadow_matcher_tokens(index, field_naming_scheme))
+        .collect::<syn::Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let mut item = SwitchItem {
+        matcher,
+        matcher_string,
+        ident,
+        fields,
+        case_sensitive: options.case_sensitive,
+        trailing_slash: options.trailing_slash,
+    };
+    resolve_optional_captures(&mut item)?;
+    Ok(item)
+}
+
 trait Flatten<T> {
     /// Because flatten is a nightly feature. I'm making a new variant of the function here for
     /// stable use. The naming is changed to avoid this getting clobbered when object_flattening
@@ -119,19 +175,42 @@ impl<T> Flatten<T> for Option<Option<T>> {
     }
 }
 
-fn build_matcher_from_tokens(tokens: &[ShadowMatcherToken]) -> TokenStream {
+/// An expression that builds the matcher.
+///
+/// This allocates, so the generated code only evaluates it once per thread,
+/// when initialising a `thread_local!` static.
+fn build_matcher_from_tokens(item: &SwitchItem) -> TokenStream {
+    let tokens = &item.matcher;
+    let settings = matcher_settings(true, item.case_sensitive, item.trailing_slash);
     quote! {
-        let settings = ::yew_router::matcher::MatcherSettings {
-            case_insensitive: true,
-        };
-        let matcher = ::yew_router::matcher::RouteMatcher {
+        ::yew_router::matcher::RouteMatcher {
             tokens: ::std::vec![#(#tokens),*],
-            settings
-        };
+            settings: #settings,
+        }
+    }
+}
+
+/// The settings used for matching derived routes.
+///
+/// `percent_encoding` is specified separately,
+/// because sections captured by `{*}` are written as they are.
+fn matcher_settings(
+    percent_encoding: bool,
+    case_sensitive: bool,
+    trailing_slash: TrailingSlash,
+) -> TokenStream {
+    let case_insensitive = !case_sensitive;
+    quote! {
+        ::yew_router::matcher::MatcherSettings {
+            case_insensitive: #case_insensitive,
+            percent_encoding: #percent_encoding,
+            trailing_slash: #trailing_slash,
+        }
     }
 }
 
 /// Enum indicating which sort of writer is needed.
+#[derive(Clone, Copy)]
 pub(crate) enum FieldType {
     Named,
     Unnamed { index: usize },
@@ -139,40 +218,409 @@ pub(crate) enum FieldType {
 }
 
 /// This assumes that the variant/struct has been destructured.
-fn write_for_token(token: &ShadowMatcherToken, naming_scheme: FieldType) -> TokenStream {
+pub(crate) fn write_for_token(
+    token: &ShadowMatcherToken,
+    naming_scheme: FieldType,
+) -> TokenStream {
     match token {
         ShadowMatcherToken::Exact(lit) => {
             quote! {
                 write!(buf, "{}", #lit).unwrap();
             }
         }
-        ShadowMatcherToken::Capture(capture) => match naming_scheme {
-            FieldType::Named | FieldType::Unit => match &capture {
-                ShadowCaptureVariant::Named(name)
-                | ShadowCaptureVariant::ManyNamed(name)
-                | ShadowCaptureVariant::NumberedNamed { name, .. } => {
-                    let name = Ident::new(&name, Span::call_site());
-                    quote! {
-                        state = state.or_else(|| #name.build_route_section(buf));
+        ShadowMatcherToken::OneOf(alternatives) => {
+            // The first alternative is the canonical one.
+            let lit = &alternatives[0];
+            quote! {
+                write!(buf, "{}", #lit).unwrap();
+            }
+        }
+        ShadowMatcherToken::Capture(capture) => {
+            // Values captured by `{*}` are usually matched again by a nested Switch,
+            // so they are neither decoded nor encoded.
+            write_for_capture(capture, naming_scheme, !capture.is_many())
+        }
+        ShadowMatcherToken::Query { continued, params } => {
+            // Parameters are written in the order they were declared.
+            // Because parameters stored in `None` fields are omitted,
+            // whether a parameter is the first to be written is only known at runtime.
+            let mut index = match naming_scheme {
+                FieldType::Unnamed { index } => index,
+                FieldType::Named | FieldType::Unit => 0,
+            };
+            let separator = if *continued {
+                quote! {"&"}
+            } else {
+                quote! {
+                    if buf.len() == __query_start { "?" } else { "&" }
+                }
+            };
+            let writers = params.iter().map(|param| {
+                let key = &param.key;
+                let (binding, value_writer) = match &param.value {
+                    ShadowQueryValue::Exact(lit) => (
+                        None,
+                        quote! {
+                            write!(buf, "{}", #lit).unwrap();
+                        },
+                    ),
+                    ShadowQueryValue::Capture(capture) => {
+                        let field_type = match naming_scheme {
+                            FieldType::Unnamed { .. } => {
+                                index += 1;
+                                FieldType::Unnamed { index: index - 1 }
+                            }
+                            FieldType::Named | FieldType::Unit => FieldType::Named,
+                        };
+                        let binding = match field_type {
+                            FieldType::Unnamed { index } => Some(unnamed_field_index_item(index)),
+                            FieldType::Named | FieldType::Unit => capture
+                                .name()
+                                .map(|name| Ident::new(name, Span::call_site())),
+                        };
+                        (binding, write_for_capture(capture, field_type, true))
                     }
+                };
+                let writer = quote! {
+                    let __separator = #separator;
+                    write!(buf, "{}{}=", __separator, #key).unwrap();
+                    #value_writer
+                };
+                let repeated = match &param.value {
+                    ShadowQueryValue::Capture(capture) => capture.is_many(),
+                    ShadowQueryValue::Exact(_) => false,
+                };
+                match binding {
+                    // Repeated keys are stored in a `Vec`, with a pair written for each element.
+                    Some(binding) if repeated => quote! {
+                        for #binding in #binding {
+                            #writer
+                        }
+                    },
+                    Some(binding) if param.omit_if_none => quote! {
+                        if let ::std::option::Option::Some(#binding) = #binding {
+                            #writer
+                        }
+                    },
+                    _ => writer,
                 }
-                ShadowCaptureVariant::Unnamed
-                | ShadowCaptureVariant::ManyUnnamed
-                | ShadowCaptureVariant::NumberedUnnamed { .. } => {
-                    panic!("Unnamed matcher sections not allowed for named field types")
+            });
+            if *continued {
+                quote! {
+                    #(#writers)*
                 }
-            },
-            FieldType::Unnamed { index } => {
-                let name = unnamed_field_index_item(index);
+            } else {
                 quote! {
-                    state = state.or_else(|| #name.build_route_section(&mut buf));
+                    let __query_start = buf.len();
+                    #(#writers)*
                 }
             }
-        },
+        }
+        ShadowMatcherToken::Optional(group) => {
+            // The group is written if all of its captured fields are present.
+            // Groups without any captures have no field to indicate their presence,
+            // so they are always omitted.
+            let (bindings, writers): (Vec<Ident>, Vec<TokenStream>) = match naming_scheme {
+                FieldType::Named | FieldType::Unit => {
+                    let bindings = group
+                        .iter()
+                        .filter_map(|token| match token {
+                            ShadowMatcherToken::Capture(capture) => capture.name(),
+                            _ => None,
+                        })
+                        .map(|name| Ident::new(name, Span::call_site()))
+                        .collect();
+                    let writers = group
+                        .iter()
+                        .map(|token| write_for_token(token, FieldType::Named))
+                        .collect();
+                    (bindings, writers)
+                }
+                FieldType::Unnamed { mut index } => {
+                    let bindings = (index..index + token.capture_count())
+                        .map(unnamed_field_index_item)
+                        .collect();
+                    let writers = group
+                        .iter()
+                        .map(|token| {
+                            if let ShadowMatcherToken::Capture(_) = token {
+                                let ts = write_for_token(token, FieldType::Unnamed { index });
+                                index += 1;
+                                ts
+                            } else {
+                                write_for_token(token, FieldType::Unit)
+                            }
+                        })
+                        .collect();
+                    (bindings, writers)
+                }
+            };
+            if bindings.is_empty() {
+                quote! {}
+            } else {
+                quote! {
+                    if let (#(::std::option::Option::Some(#bindings),)*) = (#(#bindings,)*) {
+                        #(#writers)*
+                    }
+                }
+            }
+        }
         ShadowMatcherToken::End => quote! {},
     }
 }
 
+fn write_for_capture(
+    capture: &ShadowCaptureVariant,
+    naming_scheme: FieldType,
+    percent_encoding: bool,
+) -> TokenStream {
+    // Case sensitivity and trailing slashes only affect matching, so they don't matter here.
+    let settings = matcher_settings(percent_encoding, false, TrailingSlash::Strict);
+    match naming_scheme {
+        FieldType::Named | FieldType::Unit => match &capture {
+            ShadowCaptureVariant::Named(name)
+            | ShadowCaptureVariant::ManyNamed(name)
+            | ShadowCaptureVariant::NumberedNamed { name, .. }
+            | ShadowCaptureVariant::ConstrainedNamed { name, .. } => {
+                let name = Ident::new(name, Span::call_site());
+                quote! {
+                    state = state.or_else(|| #name.build_route_section_with_settings(buf, #settings));
+                }
+            }
+            ShadowCaptureVariant::Unnamed
+            | ShadowCaptureVariant::ManyUnnamed
+            | ShadowCaptureVariant::NumberedUnnamed { .. } => {
+                // The parser rejects these for named fields, so this shouldn't be reached.
+                syn::Error::new(
+                    Span::call_site(),
+                    "Unnamed matcher sections are not allowed for named field types.",
+                )
+                .to_compile_error()
+            }
+        },
+        FieldType::Unnamed { index } => {
+            let name = unnamed_field_index_item(index);
+            quote! {
+                state = state.or_else(|| #name.build_route_section_with_settings(&mut buf, #settings));
+            }
+        }
+    }
+}
+
+/// Produces an expression that converts a captured value into the type of a field,
+/// returning from the enclosing function if the conversion fails.
+///
+/// `value` must evaluate to an `Option<String>`, which is `None` if the capture is absent.
+/// `Option` fields are populated with `None` in that case,
+/// as happens when they are captured within an optional group that wasn't matched.
+/// Other fields fall back to `Switch::key_not_available`,
+/// and then to `Default` if they are marked with `#[default]`.
+///
+/// `Vec` fields are populated from the `&` separated values of a repeated query key,
+/// which are decoded individually, failing if any of them can't be converted.
+/// They are empty if the key is absent, and empty values are kept.
+fn build_field_from_capture(value: TokenStream, field: &Field) -> TokenStream {
+    let field_ty = &field.ty;
+    let not_available = if has_default_attribute(field) {
+        quote! {
+            <#field_ty as ::yew_router::Switch>::key_not_available().or_else(|| {
+                ::std::option::Option::Some(<#field_ty as ::std::default::Default>::default())
+            })
+        }
+    } else {
+        quote! {
+            <#field_ty as ::yew_router::Switch>::key_not_available()
+        }
+    };
+    let conversion = if let Some(inner_ty) = vec_inner_type(field_ty) {
+        quote! {
+            {
+                let mut values = ::std::vec::Vec::new();
+                let mut state = state;
+                let mut failed = false;
+                for value in #value.iter().flat_map(|value| value.split('&')) {
+                    let value = if matcher.settings.percent_encoding {
+                        ::yew_router::matcher::percent_decode(value).into_owned()
+                    } else {
+                        value.to_string()
+                    };
+                    let (v, s) = <#inner_ty as ::yew_router::Switch>::from_route_part(
+                        value,
+                        state,
+                    );
+                    state = s;
+                    match v {
+                        ::std::option::Option::Some(v) => values.push(v),
+                        ::std::option::Option::None => {
+                            failed = true;
+                            break;
+                        }
+                    }
+                }
+                if failed {
+                    (::std::option::Option::None, state)
+                } else {
+                    (::std::option::Option::Some(values), state)
+                }
+            }
+        }
+    } else if let Some(inner_ty) = option_inner_type(field_ty) {
+        quote! {
+            match #value {
+                ::std::option::Option::Some(value) => {
+                    let (v, s) = <#inner_ty as ::yew_router::Switch>::from_route_part(
+                        value,
+                        state,
+                    );
+                    (v.map(::std::option::Option::Some), s)
+                }
+                ::std::option::Option::None => {
+                    (::std::option::Option::Some(::std::option::Option::None), state)
+                }
+            }
+        }
+    } else {
+        quote! {
+            match #value {
+                ::std::option::Option::Some(value) => {
+                    <#field_ty as ::yew_router::Switch>::from_route_part(
+                        value,
+                        state,
+                    )
+                }
+                ::std::option::Option::None => (#not_available, state),
+            }
+        }
+    };
+    quote! {
+        {
+            let (v, s) = #conversion;
+            match v {
+                ::std::option::Option::Some(val) => {
+                    state = s; // Set state for the next var.
+                    val
+                },
+                ::std::option::Option::None => return (::std::option::Option::None, s) // Failed
+            }
+        }
+    }
+}
+
+/// Gets `T` if the type is written as an `Option<T>`.
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    wrapped_type(ty, "Option")
+}
+
+/// Gets `T` if the type is written as a `Vec<T>`.
+fn vec_inner_type(ty: &Type) -> Option<&Type> {
+    wrapped_type(ty, "Vec")
+}
+
+/// Gets `T` if the type is written as `Wrapper<T>`, where `wrapper` is the name of `Wrapper`.
+fn wrapped_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let path = match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => &type_path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            match arguments.args.first()? {
+                GenericArgument::Type(inner_ty) => Some(inner_ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Is the field marked with `#[default]`.
+fn has_default_attribute(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| attr.path.is_ident("default"))
+}
+
+/// Finds the field that a capture is stored in.
+///
+/// `index` is the position of the capture among all of the captures in the matcher,
+/// which is used for unnamed fields.
+fn captured_field<'a>(
+    fields: &'a Fields,
+    capture: &ShadowCaptureVariant,
+    index: usize,
+) -> Option<&'a Field> {
+    match (fields, capture.name()) {
+        (Fields::Named(fields), Some(name)) => fields
+            .named
+            .iter()
+            .find(|field| field.ident.iter().any(|ident| ident == name)),
+        (Fields::Unnamed(fields), _) => fields.unnamed.iter().nth(index),
+        _ => None,
+    }
+}
+
+/// Checks that every capture that may be absent is stored in a field that can represent that.
+///
+/// Captures within optional groups must be stored in `Option` fields,
+/// optional query parameters must be stored in `Option` fields or ones marked `#[default]`,
+/// and repeated query parameters must be stored in `Vec` fields.
+/// Query parameters that are stored in `Option` fields are marked,
+/// so that they are omitted when building a route if the field is `None`.
+fn resolve_optional_captures(item: &mut SwitchItem) -> syn::Result<()> {
+    let SwitchItem {
+        matcher, fields, ..
+    } = item;
+
+    let mut index = 0;
+    for token in matcher.iter_mut() {
+        match token {
+            ShadowMatcherToken::Optional(group) => {
+                for capture in group.iter().filter_map(|token| match token {
+                    ShadowMatcherToken::Capture(capture) => Some(capture),
+                    _ => None,
+                }) {
+                    if let Some(field) = captured_field(fields, capture, index)
+                        .filter(|field| option_inner_type(&field.ty).is_none())
+                    {
+                        return Err(syn::Error::new_spanned(
+                            &field.ty,
+                            "Captures within optional groups ([...]) must be stored in an `Option` field.",
+                        ));
+                    }
+                    index += 1;
+                }
+            }
+            ShadowMatcherToken::Query { params, .. } => {
+                for param in params.iter_mut() {
+                    if let ShadowQueryValue::Capture(capture) = &param.value {
+                        if let Some(field) = captured_field(fields, capture, index) {
+                            if capture.is_many() && vec_inner_type(&field.ty).is_none() {
+                                return Err(syn::Error::new_spanned(
+                                    &field.ty,
+                                    "Repeated query parameters ({*:name}) must be stored in a `Vec` field.",
+                                ));
+                            }
+                            let is_option = option_inner_type(&field.ty).is_some();
+                            if param.optional && !is_option && !has_default_attribute(field) {
+                                return Err(syn::Error::new_spanned(
+                                    &field.ty,
+                                    "Optional query parameters ({name?}) must be stored in an `Option` field, or one marked with `#[default]`.",
+                                ));
+                            }
+                            param.omit_if_none = is_option;
+                        }
+                        index += 1;
+                    }
+                }
+            }
+            token => index += token.capture_count(),
+        }
+    }
+    Ok(())
+}
+
 
 /// Creates an ident used for destructuring unnamed fields.
 ///
@@ -1,18 +1,20 @@
 use crate::switch::shadow::{ShadowCaptureVariant, ShadowMatcherToken};
-use syn::{spanned::Spanned, Attribute, Lit, Meta, MetaNameValue};
-use yew_router_route_parser::FieldNamingScheme;
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{spanned::Spanned, Attribute, Lit, LitStr, Meta, MetaNameValue, NestedMeta};
+use yew_router_route_parser::{FieldNamingScheme, PrettyParseError};
 
 pub enum AttrToken {
-    To(String),
+    To(LitStr),
     End,
-    Rest(Option<String>),
+    Rest { name: Option<String>, span: Span },
 }
 
 impl AttrToken {
     pub fn convert_attributes_to_tokens(attributes: Vec<Attribute>) -> syn::Result<Vec<Self>> {
-        fn get_meta_name_value_str(mnv: &MetaNameValue) -> syn::Result<String> {
+        fn get_meta_name_value_str(mnv: &MetaNameValue) -> syn::Result<LitStr> {
             match &mnv.lit {
-                Lit::Str(s) => Ok(s.value()),
+                Lit::Str(s) => Ok(s.clone()),
                 lit => Err(syn::Error::new_spanned(lit, "expected a string literal")),
             }
         }
@@ -28,9 +30,12 @@ impl AttrToken {
                             .get_ident()
                             .and_then(|ident| match ident.to_string().as_str() {
                                 "to" => Some(get_meta_name_value_str(&mnv).map(AttrToken::To)),
-                                "rest" => Some(
-                                    get_meta_name_value_str(&mnv).map(|s| AttrToken::Rest(Some(s))),
-                                ),
+                                "rest" => Some(get_meta_name_value_str(&mnv).map(|s| {
+                                    AttrToken::Rest {
+                                        name: Some(s.value()),
+                                        span: meta_span,
+                                    }
+                                })),
                                 _ => None,
                             })
                     }
@@ -38,7 +43,10 @@ impl AttrToken {
                         path.get_ident()
                             .and_then(|ident| match ident.to_string().as_str() {
                                 "end" => Some(Ok(AttrToken::End)),
-                                "rest" => Some(Ok(AttrToken::Rest(None))),
+                                "rest" => Some(Ok(AttrToken::Rest {
+                                    name: None,
+                                    span: meta_span,
+                                })),
                                 _ => None,
                             })
                     }
@@ -61,28 +69,225 @@ impl AttrToken {
             .collect()
     }
 
+    /// The matcher string that the tokens are equivalent to, as it would be written in a single
+    /// `#[to]` attribute.
+    pub fn matcher_string(tokens: &[AttrToken]) -> String {
+        tokens
+            .iter()
+            .map(|token| match token {
+                AttrToken::To(lit) => lit.value(),
+                AttrToken::End => "!".to_string(),
+                AttrToken::Rest {
+                    name: Some(name), ..
+                } => format!("{{*:{}}}", name),
+                AttrToken::Rest { name: None, .. } => "{*}".to_string(),
+            })
+            .collect()
+    }
+
     /// The id is an unique identifier that allows otherwise unnamed captures to still be captured
     /// with unique names.
     pub fn into_shadow_matcher_tokens(
         self,
         id: usize,
         field_naming_scheme: FieldNamingScheme,
-    ) -> Vec<ShadowMatcherToken> {
+    ) -> syn::Result<Vec<ShadowMatcherToken>> {
         match self {
-            AttrToken::To(matcher_string) => {
-                yew_router_route_parser::parse_str_and_optimize_tokens(&matcher_string, field_naming_scheme)
-                    .expect("Invalid Matcher") // This is the point where users should see an error message if their matcher string has some syntax error.
-                    .into_iter()
-                    .map(crate::switch::shadow::ShadowMatcherToken::from)
-                    .collect()
+            AttrToken::To(lit) => {
+                let matcher_string = lit.value();
+                // This is the point where users should see an error message if their matcher
+                // string has some syntax error.
+                let tokens = yew_router_route_parser::parse_str_and_optimize_tokens(
+                    &matcher_string,
+                    field_naming_scheme,
+                )
+                .map_err(|err| parse_error(&lit, &err))?;
+                Ok(tokens.into_iter().map(ShadowMatcherToken::from).collect())
+            }
+            AttrToken::End => Ok(vec![ShadowMatcherToken::End]),
+            AttrToken::Rest { span, .. } if field_naming_scheme == FieldNamingScheme::Unit => {
+                Err(syn::Error::new(
+                    span,
+                    "`#[rest]` captures into a field, so it can't be used without any fields.",
+                ))
             }
-            AttrToken::End => vec![ShadowMatcherToken::End],
-            AttrToken::Rest(Some(capture_name)) => vec![ShadowMatcherToken::Capture(
+            AttrToken::Rest { name: None, span }
+                if field_naming_scheme == FieldNamingScheme::Named =>
+            {
+                Err(syn::Error::new(
+                    span,
+                    "`#[rest]` needs the name of the field to capture into when fields are named, like `#[rest = \"field_name\"]`.",
+                ))
+            }
+            AttrToken::Rest {
+                name: Some(capture_name),
+                ..
+            } => Ok(vec![ShadowMatcherToken::Capture(
                 ShadowCaptureVariant::ManyNamed(capture_name),
-            )],
-            AttrToken::Rest(None) => vec![ShadowMatcherToken::Capture(
+            )]),
+            AttrToken::Rest { name: None, .. } => Ok(vec![ShadowMatcherToken::Capture(
                 ShadowCaptureVariant::ManyNamed(id.to_string()),
-            )],
+            )]),
+        }
+    }
+}
+
+/// Creates an error spanned on the matcher string.
+///
+/// Where the compiler supports it, the span only covers the character at which parsing failed.
+fn parse_error(lit: &LitStr, err: &PrettyParseError) -> syn::Error {
+    let value = lit.value();
+    let position = err.position().min(value.len());
+    // Positions can only be mapped onto the literal if it is written without escapes or a raw
+    // prefix, in which case the contents start after the opening quote.
+    let is_plain = lit.token().to_string() == format!("{:?}", value);
+    let span = if is_plain && value.is_char_boundary(position) {
+        let end = value[position..]
+            .chars()
+            .next()
+            .map_or(position + 1, |c| position + c.len_utf8());
+        lit.token().subspan(position + 1..end + 1)
+    } else {
+        None
+    };
+    syn::Error::new(span.unwrap_or_else(|| lit.span()), format!("{:?}", err))
+}
+
+/// Options that apply to the whole enum or struct, set with `#[switch(...)]`.
+///
+/// Options other than the order can also be set on a variant, overriding those of the enum.
+#[derive(Default, Clone, Copy)]
+pub struct SwitchOptions {
+    /// `order = "declaration"` or `order = "specificity"`.
+    pub order: VariantOrder,
+    /// `case_sensitive`, or `case_insensitive`, which is the default.
+    pub case_sensitive: bool,
+    /// `trailing_slash = "strict"`, `"ignore"` or `"redirect"`.
+    pub trailing_slash: TrailingSlash,
+}
+
+/// The order in which the variants of an enum are tried.
+#[derive(Clone, Copy, PartialEq)]
+pub enum VariantOrder {
+    /// In the order they are declared.
+    Declaration,
+    /// The most specific variants are tried first.
+    Specificity,
+}
+
+impl Default for VariantOrder {
+    fn default() -> Self {
+        VariantOrder::Declaration
+    }
+}
+
+/// How a slash at the end of a route's path is treated,
+/// which expands to a `::yew_router::matcher::TrailingSlash`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TrailingSlash {
+    Strict,
+    Ignore,
+    Redirect,
+}
+
+impl Default for TrailingSlash {
+    fn default() -> Self {
+        TrailingSlash::Strict
+    }
+}
+
+impl TrailingSlash {
+    pub fn is_ignored(self) -> bool {
+        match self {
+            TrailingSlash::Strict => false,
+            TrailingSlash::Ignore | TrailingSlash::Redirect => true,
+        }
+    }
+}
+
+impl ToTokens for TrailingSlash {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            TrailingSlash::Strict => quote! {::yew_router::matcher::TrailingSlash::Strict},
+            TrailingSlash::Ignore => quote! {::yew_router::matcher::TrailingSlash::Ignore},
+            TrailingSlash::Redirect => quote! {::yew_router::matcher::TrailingSlash::Redirect},
+        })
+    }
+}
+
+impl SwitchOptions {
+    /// Parses the options on an enum.
+    pub fn from_attributes(attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut options = SwitchOptions::default();
+        options.apply(attributes, true)?;
+        Ok(options)
+    }
+
+    /// Parses the options on a struct or a variant, starting from these options.
+    pub fn with_item_attributes(self, attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut options = self;
+        options.apply(attributes, false)?;
+        Ok(options)
+    }
+
+    fn apply(&mut self, attributes: &[Attribute], is_enum: bool) -> syn::Result<()> {
+        for attr in attributes.iter().filter(|attr| attr.path.is_ident("switch")) {
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "Expected options within parenthesis, like `#[switch(order = \"specificity\")]`.",
+                    ))
+                }
+            };
+            for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::NameValue(mnv)) if mnv.path.is_ident("order") => {
+                        if !is_enum {
+                            return Err(syn::Error::new_spanned(
+                                mnv,
+                                "The order of variants can only be set on an enum.",
+                            ));
+                        }
+                        self.order = match &mnv.lit {
+                            Lit::Str(s) if s.value() == "declaration" => VariantOrder::Declaration,
+                            Lit::Str(s) if s.value() == "specificity" => VariantOrder::Specificity,
+                            lit => {
+                                return Err(syn::Error::new_spanned(
+                                    lit,
+                                    "Expected either \"declaration\" or \"specificity\".",
+                                ))
+                            }
+                        }
+                    }
+                    NestedMeta::Meta(Meta::NameValue(mnv))
+                        if mnv.path.is_ident("trailing_slash") =>
+                    {
+                        self.trailing_slash = match &mnv.lit {
+                            Lit::Str(s) if s.value() == "strict" => TrailingSlash::Strict,
+                            Lit::Str(s) if s.value() == "ignore" => TrailingSlash::Ignore,
+                            Lit::Str(s) if s.value() == "redirect" => TrailingSlash::Redirect,
+                            lit => {
+                                return Err(syn::Error::new_spanned(
+                                    lit,
+                                    "Expected either \"strict\", \"ignore\" or \"redirect\".",
+                                ))
+                            }
+                        }
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("case_sensitive") => {
+                        self.case_sensitive = true
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("case_insensitive") => {
+                        self.case_sensitive = false
+                    }
+                    nested => {
+                        return Err(syn::Error::new_spanned(nested, "Unknown option."));
+                    }
+                }
+            }
         }
+        Ok(())
     }
 }
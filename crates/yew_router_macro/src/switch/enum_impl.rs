@@ -1,3 +1,4 @@
+use crate::switch::ItemMethods;
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
@@ -6,11 +7,13 @@ pub use self::{build_route_section::BuildRouteSection, from_route_part::FromRout
 
 mod build_route_section;
 mod from_route_part;
+mod prefix_trie;
 
 
 pub struct EnumInner<'a> {
     pub from_route_part: FromRoutePart<'a>,
     pub build_route_section: BuildRouteSection<'a>,
+    pub item_methods: ItemMethods<'a>,
 }
 
 impl<'a> ToTokens for EnumInner<'a> {
@@ -18,10 +21,12 @@ impl<'a> ToTokens for EnumInner<'a> {
         let EnumInner {
             from_route_part,
             build_route_section,
+            item_methods,
         } = self;
         tokens.extend(quote! {
             #from_route_part
             #build_route_section
+            #item_methods
         });
     }
 }
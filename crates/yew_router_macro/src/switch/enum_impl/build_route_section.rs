@@ -1,6 +1,4 @@
-use crate::switch::{
-    shadow::ShadowMatcherToken, unnamed_field_index_item, write_for_token, FieldType, SwitchItem,
-};
+use crate::switch::{unnamed_field_index_item, write_for_token, FieldType, SwitchItem};
 use proc_macro2::{Ident, TokenStream};
 use quote::{quote, ToTokens};
 use syn::Fields;
@@ -35,6 +33,7 @@ pub fn build_serializer_for_enum(
             matcher,
             ident,
             fields,
+            ..
         } = switch_item;
         match fields {
             Fields::Named(fields_named) => {
@@ -59,9 +58,9 @@ pub fn build_serializer_for_enum(
                     .map(|(index, _)| unnamed_field_index_item(index));
                 let mut item_count = 0;
                 let writers = matcher.iter().map(|token| {
-                    if let ShadowMatcherToken::Capture(_) = &token {
+                    if token.capture_count() > 0 {
                         let ts = write_for_token(token, FieldType::Unnamed { index: item_count });
-                        item_count += 1;
+                        item_count += token.capture_count();
                         ts
                     } else {
                         // Its either a literal, or something that will panic currently
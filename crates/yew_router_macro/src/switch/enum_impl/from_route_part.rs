@@ -1,7 +1,8 @@
-use crate::switch::SwitchItem;
+use super::prefix_trie::PrefixTrie;
+use crate::switch::{build_field_from_capture, SwitchItem};
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
-use syn::{Field, Fields, Type};
+use syn::{Field, Fields};
 
 pub struct FromRoutePart<'a> {
     pub switch_variants: &'a [SwitchItem],
@@ -11,27 +12,42 @@ pub struct FromRoutePart<'a> {
 
 impl<'a> ToTokens for FromRoutePart<'a> {
     fn to_tokens(&self, tokens: &mut TokenStream) {
+        let variant_count = self.switch_variants.len();
+        let indices = 0..variant_count;
+        let matchers = self
+            .switch_variants
+            .iter()
+            .map(super::super::build_matcher_from_tokens);
         let variant_matchers = self.switch_variants.iter().map(|sv| {
-            let SwitchItem {
-                matcher,
-                ident,
-                fields,
-            } = sv;
-            let build_from_captures = build_variant_from_captures(&self.enum_ident, ident, fields);
-            let matcher = super::super::build_matcher_from_tokens(&matcher);
-
-            quote! {
-                #matcher
-                #build_from_captures
-            }
+            let SwitchItem { ident, fields, .. } = sv;
+            build_variant_from_captures(self.enum_ident, ident, fields)
         });
 
+        let prefix_trie = PrefixTrie::new(self.switch_variants);
+
+        // Only the variants whose leading literal begins the route are tried,
+        // in the same order as they would be if every variant was tried.
+        // The matchers are built the first time they are used, rather than on every call.
         tokens.extend(quote!{
             fn from_route_part<__T>(route: String, mut state: Option<__T>) -> (::std::option::Option<Self>, ::std::option::Option<__T>) {
                 let route_string = route;
-                #(#variant_matchers)*
+                static PREFIX_TRIE: ::yew_router::matcher::PrefixTrie = #prefix_trie;
+                ::std::thread_local! {
+                    static MATCHERS: [::yew_router::matcher::RouteMatcher; #variant_count] = [#(#matchers),*];
+                }
+                MATCHERS.with(move |matchers| {
+                    for index in PREFIX_TRIE.candidates(&route_string) {
+                        let matcher = &matchers[index];
+                        match index {
+                            #(#indices => {
+                                #variant_matchers
+                            })*
+                            _ => {}
+                        }
+                    }
 
-                (::std::option::Option::None, state)
+                    (::std::option::Option::None, state)
+                })
             }
         });
     }
@@ -49,37 +65,18 @@ fn build_variant_from_captures(
                 .named
                 .iter()
                 .filter_map(|field: &Field| {
-                    let field_ty: &Type = &field.ty;
                     field.ident.as_ref().map(|i: &Ident| {
                         let key = i.to_string();
-                        (i, key, field_ty)
+                        (i, key, field)
                     })
                 })
-                .map(|(field_name, key, field_ty): (&Ident, String, &Type)| {
+                .map(|(field_name, key, field): (&Ident, String, &Field)| {
+                    let field_value = build_field_from_capture(
+                        quote! {captures.remove(#key).map(::std::borrow::Cow::into_owned)},
+                        field,
+                    );
                     let field_decl = quote! {
-                        let #field_name = {
-                            let (v, s) = match captures.remove(#key) {
-                                ::std::option::Option::Some(value) => {
-                                    <#field_ty as ::yew_router::Switch>::from_route_part(
-                                        value,
-                                        state,
-                                    )
-                                }
-                                ::std::option::Option::None => {
-                                    (
-                                        <#field_ty as ::yew_router::Switch>::key_not_available(),
-                                        state,
-                                    )
-                                }
-                            };
-                            match v {
-                                ::std::option::Option::Some(val) => {
-                                    state = s; // Set state for the next var.
-                                    val
-                                },
-                                ::std::option::Option::None => return (None, s) // Failed
-                            }
-                        };
+                        let #field_name = #field_value;
                     };
 
                     (field_decl, field_name)
@@ -87,7 +84,7 @@ fn build_variant_from_captures(
                 .unzip();
 
             quote! {
-                let mut state = if let ::std::option::Option::Some(mut captures) = matcher
+                state = if let ::std::option::Option::Some(mut captures) = matcher
                     .capture_route_into_map(&route_string)
                     .ok()
                     .map(|x| x.1)
@@ -120,32 +117,15 @@ fn build_variant_from_captures(
                 .iter()
                 .enumerate()
                 .map(|(idx, f)| {
-                    let field_ty = &f.ty;
                     let field_var_name = Ident::new(&format!("field_{}", idx), Span::call_site());
+                    let field_value = build_field_from_capture(
+                        quote! {
+                            drain.next().and_then(|value| value).map(::std::borrow::Cow::into_owned)
+                        },
+                        f,
+                    );
                     let field_decl = quote! {
-                        let #field_var_name = {
-                            let (v, s) = match drain.next() {
-                                ::std::option::Option::Some(value) => {
-                                    <#field_ty as ::yew_router::Switch>::from_route_part(
-                                        value,
-                                        state,
-                                    )
-                                },
-                                ::std::option::Option::None => {
-                                    (
-                                        <#field_ty as ::yew_router::Switch>::key_not_available(),
-                                        state,
-                                    )
-                                }
-                            };
-                            match v {
-                                ::std::option::Option::Some(val) => {
-                                    state = s; // Set state for the next var.
-                                    val
-                                },
-                                ::std::option::Option::None => return (None, s) // Failed
-                            }
-                        };
+                        let #field_var_name = #field_value;
                     };
 
                     (field_decl, field_var_name)
@@ -153,8 +133,8 @@ fn build_variant_from_captures(
                 .unzip();
 
             quote! {
-                let mut state = if let ::std::option::Option::Some(mut captures) = matcher
-                    .capture_route_into_vec(&route_string)
+                state = if let ::std::option::Option::Some(mut captures) = matcher
+                    .capture_route_into_optional_vec(&route_string)
                     .ok()
                     .map(|x| x.1)
                 {
@@ -183,7 +163,7 @@ fn build_variant_from_captures(
         }
         Fields::Unit => {
             quote! {
-                let mut state = if let ::std::option::Option::Some(_captures) = matcher.capture_route_into_map(&route_string).ok().map(|x| x.1) {
+                state = if let ::std::option::Option::Some(_captures) = matcher.capture_route_into_map(&route_string).ok().map(|x| x.1) {
                     return (::std::option::Option::Some(#enum_ident::#variant_ident), state);
                 } else {
                     state
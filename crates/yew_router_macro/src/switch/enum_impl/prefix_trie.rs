@@ -0,0 +1,97 @@
+use crate::switch::{shadow::ShadowMatcherToken, SwitchItem};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+/// A radix trie over the literals that each variant's matcher begins with,
+/// which expands to a `::yew_router::matcher::PrefixTrie`.
+///
+/// The items are the indices of the variants.
+#[derive(Default)]
+pub struct PrefixTrie {
+    prefix: String,
+    items: Vec<usize>,
+    children: Vec<PrefixTrie>,
+}
+
+impl PrefixTrie {
+    pub fn new(switch_variants: &[SwitchItem]) -> Self {
+        let mut root = PrefixTrie::default();
+        for (index, variant) in switch_variants.iter().enumerate() {
+            root.insert(&leading_literal(variant), index);
+        }
+        root
+    }
+
+    fn insert(&mut self, key: &str, index: usize) {
+        if key.is_empty() {
+            self.items.push(index);
+            return;
+        }
+        for child in &mut self.children {
+            let common = common_prefix_len(&child.prefix, key);
+            if common == 0 {
+                continue;
+            }
+            if common < child.prefix.len() {
+                // Split the child, so that it ends where the key diverges from it.
+                let suffix = PrefixTrie {
+                    prefix: child.prefix[common..].to_string(),
+                    items: std::mem::take(&mut child.items),
+                    children: std::mem::take(&mut child.children),
+                };
+                child.prefix.truncate(common);
+                child.children.push(suffix);
+            }
+            child.insert(&key[common..], index);
+            return;
+        }
+        self.children.push(PrefixTrie {
+            prefix: key.to_string(),
+            items: vec![index],
+            children: vec![],
+        });
+    }
+}
+
+impl ToTokens for PrefixTrie {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let PrefixTrie {
+            prefix,
+            items,
+            children,
+        } = self;
+        tokens.extend(quote! {
+            ::yew_router::matcher::PrefixTrie {
+                prefix: #prefix,
+                items: &[#(#items),*],
+                children: &[#(#children),*],
+            }
+        })
+    }
+}
+
+/// The lowercase literal that every route matched by the variant must begin with.
+///
+/// This stops at the first character that isn't ASCII,
+/// because the trie only compares literals without regard to ASCII case.
+fn leading_literal(variant: &SwitchItem) -> String {
+    match variant.matcher.first() {
+        Some(ShadowMatcherToken::Exact(literal)) => {
+            let mut literal = literal.as_str();
+            if variant.trailing_slash.is_ignored() && literal.ends_with('/') {
+                // The slash may be missing from the end of the route.
+                literal = &literal[..literal.len() - 1];
+            }
+            literal
+                .chars()
+                .take_while(char::is_ascii)
+                .map(|c| c.to_ascii_lowercase())
+                .collect()
+        }
+        _ => String::new(),
+    }
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.bytes().zip(b.bytes()).take_while(|(a, b)| a == b).count()
+}
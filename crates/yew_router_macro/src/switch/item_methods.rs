@@ -0,0 +1,104 @@
+use crate::switch::{attribute::TrailingSlash, build_matcher_from_tokens, SwitchItem};
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, ToTokens};
+use syn::Fields;
+
+/// Implements the methods of `Switch` that describe the items, rather than matching or building
+/// routes: `trailing_slash`, `static_routes` and `route_info`.
+///
+/// Methods whose result would be the same as the default implementation are not produced.
+pub struct ItemMethods<'a> {
+    pub switch_items: &'a [SwitchItem],
+    /// The enum the items are variants of, or `None` if the only item is a struct.
+    pub enum_ident: Option<&'a Ident>,
+}
+
+impl<'a> ItemMethods<'a> {
+    /// The path of the item, as a value or as a pattern when followed by `{ .. }`.
+    fn item_path(&self, item: &SwitchItem) -> TokenStream {
+        let ident = &item.ident;
+        match self.enum_ident {
+            Some(enum_ident) => quote! {#enum_ident::#ident},
+            None => quote! {#ident},
+        }
+    }
+
+    /// Reports the trailing slash policy of the item that was switched.
+    fn trailing_slash(&self) -> Option<TokenStream> {
+        if self
+            .switch_items
+            .iter()
+            .all(|item| item.trailing_slash == TrailingSlash::Strict)
+        {
+            return None;
+        }
+        let arms = self.switch_items.iter().map(|item| {
+            let path = self.item_path(item);
+            let policy = item.trailing_slash;
+            quote! {
+                #path { .. } => #policy,
+            }
+        });
+        Some(quote! {
+            fn trailing_slash(&self) -> ::yew_router::matcher::TrailingSlash {
+                match *self {
+                    #(#arms)*
+                }
+            }
+        })
+    }
+
+    /// Lists the items whose matchers have no captures.
+    ///
+    /// Only unit items can be listed, because every field has to be filled by a capture.
+    fn static_routes(&self) -> Option<TokenStream> {
+        let values: Vec<TokenStream> = self
+            .switch_items
+            .iter()
+            .filter(|item| matches!(item.fields, Fields::Unit))
+            .map(|item| self.item_path(item))
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        Some(quote! {
+            fn static_routes() -> ::std::vec::Vec<Self> {
+                ::std::vec![#(#values),*]
+            }
+        })
+    }
+
+    /// Describes the matcher of every item.
+    fn route_info(&self) -> TokenStream {
+        let infos = self.switch_items.iter().map(|item| {
+            let ident = item.ident.to_string();
+            let matcher_string = &item.matcher_string;
+            let matcher = build_matcher_from_tokens(item);
+            let field_types = item.fields.iter().map(|field| {
+                let ty = &field.ty;
+                quote! {::std::stringify!(#ty)}
+            });
+            quote! {
+                ::yew_router::switch::RouteInfo {
+                    ident: #ident,
+                    matcher_string: #matcher_string,
+                    matcher: #matcher,
+                    field_types: ::std::vec![#(#field_types),*],
+                }
+            }
+        });
+        quote! {
+            fn route_info() -> ::std::vec::Vec<::yew_router::switch::RouteInfo> {
+                ::std::vec![#(#infos),*]
+            }
+        }
+    }
+}
+
+impl<'a> ToTokens for ItemMethods<'a> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(self.trailing_slash());
+        tokens.extend(self.static_routes());
+        tokens.extend(self.route_info());
+    }
+}
@@ -0,0 +1,358 @@
+//! Detects enum variants that can never be matched, because an earlier variant matches every
+//! route that they would.
+use crate::switch::{
+    captured_field,
+    shadow::{ShadowCaptureVariant, ShadowMatcherToken},
+    SwitchItem,
+};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Attribute, Ident, Type};
+
+/// Produces warnings for every variant that is shadowed by an earlier one.
+///
+/// Proc macros can't emit warnings directly on stable,
+/// so each warning is produced by using a deprecated item at the span of the shadowed variant.
+/// Lint attributes on the enum and on the shadowed variant are applied to that use,
+/// so the warning can be silenced with `#[allow(deprecated)]`.
+///
+/// `variant_lints` holds the lint attributes of each variant, in the same order as `variants`.
+pub fn shadowed_variant_warnings(
+    variants: &[SwitchItem],
+    enum_lints: &[Attribute],
+    variant_lints: &[Vec<Attribute>],
+) -> TokenStream {
+    let warnings = variants
+        .iter()
+        .enumerate()
+        .filter_map(|(index, variant)| {
+            let later = atoms(variant);
+            variants[..index]
+                .iter()
+                .find(|earlier| subsumes(earlier, &atoms(earlier), variant, &later))
+                .map(|earlier| (index, earlier, variant))
+        })
+        .map(|(index, earlier, shadowed)| {
+            let note = format!(
+                "`{}` can never be matched, because `{}` is tried first and matches every route that it would.",
+                shadowed.ident, earlier.ident
+            );
+            let name = format!("__ShadowedVariant{}", index);
+            let warning = Ident::new(&name, Span::call_site());
+            // The warning is reported where this is used.
+            let usage = Ident::new(&name, shadowed.ident.span());
+            let user = Ident::new(&format!("__warn_shadowed_variant_{}", index), Span::call_site());
+            let lints = &variant_lints[index];
+            quote! {
+                #[deprecated(note = #note)]
+                struct #warning;
+                #(#enum_lints)*
+                #(#lints)*
+                fn #user() {
+                    let _ = #usage;
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if warnings.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            #[allow(dead_code)]
+            const _: () = {
+                #(#warnings)*
+            };
+        }
+    }
+}
+
+/// Gets the attributes that control lint levels, like `#[allow(...)]`.
+pub fn lint_attributes(attrs: &[Attribute]) -> Vec<Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| {
+            ["allow", "warn", "deny", "forbid"]
+                .iter()
+                .any(|lint| attr.path.is_ident(lint))
+        })
+        .cloned()
+        .collect()
+}
+
+/// The smallest parts of a matcher that are compared.
+enum Atom<'a> {
+    /// A literal character, as it was declared.
+    Char(char),
+    /// A capture of a single section, and the type of the field it is stored in.
+    Capture(&'a ShadowCaptureVariant, Option<&'a Type>),
+    End,
+    /// Anything that isn't compared, which prevents any conclusions from being made.
+    Unknown,
+}
+
+fn atoms(item: &SwitchItem) -> Vec<Atom<'_>> {
+    let mut atoms = vec![];
+    let mut index = 0;
+    for token in &item.matcher {
+        match token {
+            ShadowMatcherToken::Exact(literal) => {
+                atoms.extend(literal.chars().map(Atom::Char))
+            }
+            ShadowMatcherToken::Capture(capture) if captures_single_section(capture) => {
+                let ty = captured_field(&item.fields, capture, index).map(|field| &field.ty);
+                atoms.push(Atom::Capture(capture, ty));
+                index += 1;
+            }
+            ShadowMatcherToken::End => atoms.push(Atom::End),
+            _ => {
+                // Nothing after this point can be compared.
+                atoms.push(Atom::Unknown);
+                break;
+            }
+        }
+    }
+    atoms
+}
+
+fn captures_single_section(capture: &ShadowCaptureVariant) -> bool {
+    match capture {
+        ShadowCaptureVariant::Named(_)
+        | ShadowCaptureVariant::Unnamed
+        | ShadowCaptureVariant::ConstrainedNamed { .. } => true,
+        ShadowCaptureVariant::ManyNamed(_)
+        | ShadowCaptureVariant::ManyUnnamed
+        | ShadowCaptureVariant::NumberedNamed { .. }
+        | ShadowCaptureVariant::NumberedUnnamed { .. } => false,
+    }
+}
+
+/// Does a capture always succeed for any non-empty section.
+///
+/// This is the case for unconstrained captures stored in a `String`.
+fn is_total(capture: &ShadowCaptureVariant, ty: Option<&Type>) -> bool {
+    let is_string = match ty {
+        Some(Type::Path(type_path)) => {
+            type_path.qself.is_none() && type_path.path.is_ident("String")
+        }
+        _ => false,
+    };
+    let is_unconstrained = !matches!(capture, ShadowCaptureVariant::ConstrainedNamed { .. });
+    is_string && is_unconstrained
+}
+
+/// Can the character be captured by a capture that isn't followed by a literal.
+fn is_capturable(c: char) -> bool {
+    !" */#&?{}=".contains(c)
+}
+
+/// Does a literal character in the earlier matcher match every character that one in the later
+/// matcher would.
+fn char_subsumes(c: char, earlier: &SwitchItem, other: char, later: &SwitchItem) -> bool {
+    if !earlier.case_sensitive {
+        c.to_lowercase().eq(other.to_lowercase())
+    } else if !later.case_sensitive {
+        // The later matcher also matches other cases of the character, unless it has none.
+        c == other && !c.is_lowercase() && !c.is_uppercase()
+    } else {
+        c == other
+    }
+}
+
+/// Does the `earlier` matcher match every route that the `later` one would.
+///
+/// Matching only requires a prefix of the route to be consumed, unless the matcher ends with `!`,
+/// so the later matcher is subsumed if the earlier one matches the start of everything it does.
+/// This errs on the side of not reporting anything when the matchers can't be compared.
+fn subsumes(
+    earlier_item: &SwitchItem,
+    earlier: &[Atom],
+    later_item: &SwitchItem,
+    later: &[Atom],
+) -> bool {
+    if later_item.trailing_slash.is_ignored() && !earlier_item.trailing_slash.is_ignored() {
+        // The later matcher might match a route without a slash that the earlier one requires.
+        return false;
+    }
+    let mut later_index = 0;
+    for atom in earlier {
+        match atom {
+            Atom::Char(c) => match later.get(later_index) {
+                Some(Atom::Char(other)) if char_subsumes(*c, earlier_item, *other, later_item) => {
+                    later_index += 1
+                }
+                _ => return false,
+            },
+            Atom::Capture(capture, ty) => {
+                if !is_total(capture, *ty) {
+                    return false;
+                }
+                match later.get(later_index) {
+                    Some(Atom::Capture(..)) => later_index += 1,
+                    Some(Atom::Char(_)) => {
+                        let section = later[later_index..]
+                            .iter()
+                            .take_while(|atom| match atom {
+                                Atom::Char(c) => is_capturable(*c),
+                                _ => false,
+                            })
+                            .count();
+                        if section == 0 {
+                            return false;
+                        }
+                        later_index += section;
+                    }
+                    _ => return false,
+                }
+            }
+            Atom::End => {
+                return matches!(later.get(later_index), Some(Atom::End));
+            }
+            Atom::Unknown => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::switch::{attribute::SwitchOptions, switch_item};
+    use syn::{parse_quote, Variant};
+
+    fn item(variant: Variant) -> SwitchItem {
+        let options = SwitchOptions::default()
+            .with_item_attributes(&variant.attrs)
+            .unwrap();
+        switch_item(variant.ident, variant.attrs, variant.fields, options).unwrap()
+    }
+
+    /// Does the earlier variant shadow the later one.
+    fn shadows(earlier: Variant, later: Variant) -> bool {
+        let (earlier, later) = (item(earlier), item(later));
+        subsumes(&earlier, &atoms(&earlier), &later, &atoms(&later))
+    }
+
+    #[test]
+    fn literal_prefix_shadows_longer_literal() {
+        assert!(shadows(
+            parse_quote!(#[to = "/user"] A),
+            parse_quote!(#[to = "/user/new"] B)
+        ));
+        assert!(!shadows(
+            parse_quote!(#[to = "/user/new"] A),
+            parse_quote!(#[to = "/user"] B)
+        ));
+        // Matching only requires a prefix of the route to be consumed.
+        assert!(shadows(
+            parse_quote!(#[to = "/user"] A),
+            parse_quote!(#[to = "/users"] B)
+        ));
+        assert!(!shadows(
+            parse_quote!(#[to = "/users"] A),
+            parse_quote!(#[to = "/user/s"] B)
+        ));
+    }
+
+    #[test]
+    fn string_capture_shadows_literal_section() {
+        assert!(shadows(
+            parse_quote!(#[to = "/user/{name}"] A { name: String }),
+            parse_quote!(#[to = "/user/new"] B)
+        ));
+        assert!(shadows(
+            parse_quote!(#[to = "/user/{name}"] A { name: String }),
+            parse_quote!(#[to = "/user/{id}"] B { id: u32 })
+        ));
+        // A capture can't match an empty section.
+        assert!(!shadows(
+            parse_quote!(#[to = "/user/{name}"] A { name: String }),
+            parse_quote!(#[to = "/user/"] B)
+        ));
+    }
+
+    #[test]
+    fn typed_or_constrained_capture_does_not_shadow() {
+        assert!(!shadows(
+            parse_quote!(#[to = "/user/{id}"] A { id: u32 }),
+            parse_quote!(#[to = "/user/new"] B)
+        ));
+        assert!(!shadows(
+            parse_quote!(#[to = "/user/{name:[a-z]+}"] A { name: String }),
+            parse_quote!(#[to = "/user/new"] B)
+        ));
+        assert!(!shadows(
+            parse_quote!(#[to = "/user/{id}"] A { id: u32 }),
+            parse_quote!(#[to = "/user/{name}"] B { name: String })
+        ));
+    }
+
+    #[test]
+    fn end_only_shadows_end() {
+        assert!(!shadows(
+            parse_quote!(#[to = "/user!"] A),
+            parse_quote!(#[to = "/user/new"] B)
+        ));
+        assert!(shadows(
+            parse_quote!(#[to = "/user!"] A),
+            parse_quote!(#[to = "/user!"] B)
+        ));
+        assert!(shadows(
+            parse_quote!(#[to = "/user"] A),
+            parse_quote!(#[to = "/user!"] B)
+        ));
+    }
+
+    #[test]
+    fn case_sensitivity() {
+        assert!(shadows(
+            parse_quote!(#[to = "/About"] A),
+            parse_quote!(#[to = "/about"] B)
+        ));
+        assert!(!shadows(
+            parse_quote!(#[to = "/about"] #[switch(case_sensitive)] A),
+            parse_quote!(#[to = "/about"] B)
+        ));
+        assert!(shadows(
+            parse_quote!(#[to = "/about"] A),
+            parse_quote!(#[to = "/about"] #[switch(case_sensitive)] B)
+        ));
+        assert!(!shadows(
+            parse_quote!(#[to = "/About"] #[switch(case_sensitive)] A),
+            parse_quote!(#[to = "/about"] #[switch(case_sensitive)] B)
+        ));
+        // Characters without case are matched the same way either way.
+        assert!(shadows(
+            parse_quote!(#[to = "/1"] #[switch(case_sensitive)] A),
+            parse_quote!(#[to = "/1"] B)
+        ));
+    }
+
+    #[test]
+    fn trailing_slash_policies() {
+        assert!(!shadows(
+            parse_quote!(#[to = "/about"] A),
+            parse_quote!(#[to = "/about"] #[switch(trailing_slash = "ignore")] B)
+        ));
+        assert!(shadows(
+            parse_quote!(#[to = "/about"] #[switch(trailing_slash = "ignore")] A),
+            parse_quote!(#[to = "/about"] #[switch(trailing_slash = "redirect")] B)
+        ));
+        assert!(shadows(
+            parse_quote!(#[to = "/about"] #[switch(trailing_slash = "ignore")] A),
+            parse_quote!(#[to = "/about"] B)
+        ));
+    }
+
+    #[test]
+    fn uncomparable_tokens_do_not_shadow() {
+        assert!(!shadows(
+            parse_quote!(#[to = "/user[/{id}]"] A { id: Option<String> }),
+            parse_quote!(#[to = "/user"] B)
+        ));
+        assert!(!shadows(
+            parse_quote!(#[to = "{*:rest}"] A { rest: String }),
+            parse_quote!(#[to = "/user"] B)
+        ));
+    }
+}
@@ -1,6 +1,9 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens};
-use yew_router_route_parser::{CaptureVariant, MatcherToken};
+use syn::Ident;
+use yew_router_route_parser::{
+    CaptureConstraint, CaptureVariant, MatcherToken, PrimitiveType, QueryValue,
+};
 
 impl ToTokens for ShadowMatcherToken {
     fn to_tokens(&self, ts: &mut TokenStream) {
@@ -12,6 +15,20 @@ impl ToTokens for ShadowMatcherToken {
             SOT::Capture(variant) => quote! {
                 ::yew_router::matcher::MatcherToken::Capture(#variant)
             },
+            SOT::OneOf(alternatives) => quote! {
+                ::yew_router::matcher::MatcherToken::OneOf(
+                    ::std::vec![#(#alternatives.to_string()),*]
+                )
+            },
+            SOT::Query { continued, params } => quote! {
+                ::yew_router::matcher::MatcherToken::Query {
+                    continued: #continued,
+                    params: ::std::vec![#(#params),*],
+                }
+            },
+            SOT::Optional(group) => quote! {
+                ::yew_router::matcher::MatcherToken::Optional(::std::vec![#(#group),*])
+            },
             SOT::End => quote! {
                 ::yew_router::matcher::MatcherToken::End
             },
@@ -25,9 +42,72 @@ impl ToTokens for ShadowMatcherToken {
 pub enum ShadowMatcherToken {
     Exact(String),
     Capture(ShadowCaptureVariant),
+    OneOf(Vec<String>),
+    Optional(Vec<ShadowMatcherToken>),
+    Query {
+        continued: bool,
+        params: Vec<ShadowQueryParam>,
+    },
     End,
 }
 
+pub struct ShadowQueryParam {
+    pub key: String,
+    pub value: ShadowQueryValue,
+    pub optional: bool,
+    /// The captured field is an `Option`, so the parameter is omitted if it is `None`.
+    ///
+    /// This isn't part of the matcher, so it is set once the fields are known.
+    pub omit_if_none: bool,
+}
+
+pub enum ShadowQueryValue {
+    Exact(String),
+    Capture(ShadowCaptureVariant),
+}
+
+impl ToTokens for ShadowQueryParam {
+    fn to_tokens(&self, ts: &mut TokenStream) {
+        let key = &self.key;
+        let optional = self.optional;
+        let value = match &self.value {
+            ShadowQueryValue::Exact(s) => quote! {
+                ::yew_router::matcher::QueryValue::Exact(#s.to_string())
+            },
+            ShadowQueryValue::Capture(variant) => quote! {
+                ::yew_router::matcher::QueryValue::Capture(#variant)
+            },
+        };
+        ts.extend(quote! {
+            ::yew_router::matcher::QueryParam {
+                key: #key.to_string(),
+                value: #value,
+                optional: #optional,
+            }
+        })
+    }
+}
+
+impl ShadowMatcherToken {
+    /// The number of capture sections within this token.
+    pub fn capture_count(&self) -> usize {
+        match self {
+            ShadowMatcherToken::Capture(_) => 1,
+            ShadowMatcherToken::Optional(group) => group.iter().map(Self::capture_count).sum(),
+            ShadowMatcherToken::Query { params, .. } => params
+                .iter()
+                .filter(|param| match param.value {
+                    ShadowQueryValue::Capture(_) => true,
+                    ShadowQueryValue::Exact(_) => false,
+                })
+                .count(),
+            ShadowMatcherToken::Exact(_)
+            | ShadowMatcherToken::OneOf(_)
+            | ShadowMatcherToken::End => 0,
+        }
+    }
+}
+
 pub enum ShadowCaptureVariant {
     /// {}
     Unnamed,
@@ -44,6 +124,72 @@ pub enum ShadowCaptureVariant {
     ManyNamed(String),
     /// {2:name} - captures a fixed number of sections with a given name.
     NumberedNamed { sections: usize, name: String },
+    /// {name:constraint} - captures a section if it satisfies the constraint.
+    ConstrainedNamed {
+        name: String,
+        constraint: ShadowCaptureConstraint,
+    },
+}
+
+impl ShadowCaptureVariant {
+    /// The name of the field this captures into, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            ShadowCaptureVariant::Named(name)
+            | ShadowCaptureVariant::ManyNamed(name)
+            | ShadowCaptureVariant::NumberedNamed { name, .. }
+            | ShadowCaptureVariant::ConstrainedNamed { name, .. } => Some(name),
+            ShadowCaptureVariant::Unnamed
+            | ShadowCaptureVariant::ManyUnnamed
+            | ShadowCaptureVariant::NumberedUnnamed { .. } => None,
+        }
+    }
+
+    /// The name of the field this captures into, so that it can be renamed.
+    pub fn name_mut(&mut self) -> Option<&mut String> {
+        match self {
+            ShadowCaptureVariant::Named(name)
+            | ShadowCaptureVariant::ManyNamed(name)
+            | ShadowCaptureVariant::NumberedNamed { name, .. }
+            | ShadowCaptureVariant::ConstrainedNamed { name, .. } => Some(name),
+            ShadowCaptureVariant::Unnamed
+            | ShadowCaptureVariant::ManyUnnamed
+            | ShadowCaptureVariant::NumberedUnnamed { .. } => None,
+        }
+    }
+
+    /// Does this capture everything, like `{*}`.
+    ///
+    /// Within a query, these capture every value of a repeated key.
+    pub fn is_many(&self) -> bool {
+        matches!(
+            self,
+            ShadowCaptureVariant::ManyNamed(_) | ShadowCaptureVariant::ManyUnnamed
+        )
+    }
+}
+
+pub enum ShadowCaptureConstraint {
+    /// {name:u32}
+    Primitive(PrimitiveType),
+    /// {name:[a-z]+}
+    CharacterClass { class: String, allow_empty: bool },
+}
+
+impl ToTokens for ShadowCaptureConstraint {
+    fn to_tokens(&self, ts: &mut TokenStream) {
+        let t = match self {
+            ShadowCaptureConstraint::Primitive(primitive) => {
+                // The variants of PrimitiveType are named after their Debug representation.
+                let primitive = Ident::new(&format!("{:?}", primitive), Span::call_site());
+                quote! {::yew_router::matcher::CaptureConstraint::Primitive(::yew_router::matcher::PrimitiveType::#primitive)}
+            }
+            ShadowCaptureConstraint::CharacterClass { class, allow_empty } => {
+                quote! {::yew_router::matcher::CaptureConstraint::CharacterClass{class: #class.to_string(), allow_empty: #allow_empty}}
+            }
+        };
+        ts.extend(t)
+    }
 }
 
 impl ToTokens for ShadowCaptureVariant {
@@ -58,6 +204,9 @@ impl ToTokens for ShadowCaptureVariant {
             ShadowCaptureVariant::NumberedNamed { sections, name } => {
                 quote! {::yew_router::matcher::CaptureVariant::NumberedNamed{sections: #sections, name: #name.to_string()}}
             }
+            ShadowCaptureVariant::ConstrainedNamed { name, constraint } => {
+                quote! {::yew_router::matcher::CaptureVariant::ConstrainedNamed{name: #name.to_string(), constraint: #constraint}}
+            }
             ShadowCaptureVariant::Unnamed => {
                 quote! {::yew_router::matcher::CaptureVariant::Unnamed}
             }
@@ -79,6 +228,25 @@ impl From<MatcherToken> for ShadowMatcherToken {
         match mt {
             MT::Exact(s) => SOT::Exact(s),
             MT::Capture(capture) => SOT::Capture(capture.into()),
+            MT::OneOf(alternatives) => SOT::OneOf(alternatives),
+            MT::Optional(group) => SOT::Optional(group.into_iter().map(SOT::from).collect()),
+            MT::Query { continued, params } => SOT::Query {
+                continued,
+                params: params
+                    .into_iter()
+                    .map(|param| ShadowQueryParam {
+                        key: param.key,
+                        value: match param.value {
+                            QueryValue::Exact(s) => ShadowQueryValue::Exact(s),
+                            QueryValue::Capture(capture) => {
+                                ShadowQueryValue::Capture(capture.into())
+                            }
+                        },
+                        optional: param.optional,
+                        omit_if_none: false,
+                    })
+                    .collect(),
+            },
             MT::End => SOT::End,
         }
     }
@@ -93,9 +261,24 @@ impl From<CaptureVariant> for ShadowCaptureVariant {
             CaptureVariant::NumberedNamed { sections, name } => {
                 SCV::NumberedNamed { sections, name }
             }
+            CaptureVariant::ConstrainedNamed { name, constraint } => SCV::ConstrainedNamed {
+                name,
+                constraint: constraint.into(),
+            },
             CaptureVariant::Unnamed => SCV::Unnamed,
             CaptureVariant::ManyUnnamed => SCV::ManyUnnamed,
             CaptureVariant::NumberedUnnamed { sections } => SCV::NumberedUnnamed { sections },
         }
     }
 }
+
+impl From<CaptureConstraint> for ShadowCaptureConstraint {
+    fn from(cc: CaptureConstraint) -> Self {
+        match cc {
+            CaptureConstraint::Primitive(primitive) => ShadowCaptureConstraint::Primitive(primitive),
+            CaptureConstraint::CharacterClass { class, allow_empty } => {
+                ShadowCaptureConstraint::CharacterClass { class, allow_empty }
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Ranks matchers by how specific they are, so that enum variants can be tried in that order.
+use crate::switch::{
+    shadow::{ShadowMatcherToken, ShadowQueryValue},
+    SwitchItem,
+};
+use std::cmp::Reverse;
+
+/// How specific a matcher is, where greater values are more specific.
+///
+/// Matchers are compared by the length of the literals they require,
+/// then by how few `{*}` captures they have, then by how few other captures they have,
+/// and finally by whether they end with `!`.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub struct Specificity {
+    literal_length: usize,
+    many_captures: Reverse<usize>,
+    captures: Reverse<usize>,
+    ends: bool,
+}
+
+impl Specificity {
+    pub fn of(item: &SwitchItem) -> Self {
+        let mut literal_length = 0;
+        let mut many_captures = 0;
+        let mut captures = 0;
+        let mut ends = false;
+        for token in &item.matcher {
+            match token {
+                ShadowMatcherToken::Exact(literal) => literal_length += literal.chars().count(),
+                ShadowMatcherToken::OneOf(alternatives) => {
+                    literal_length += alternatives
+                        .iter()
+                        .map(|alternative| alternative.chars().count())
+                        .min()
+                        .unwrap_or(0)
+                }
+                ShadowMatcherToken::Capture(capture) if capture.is_many() => many_captures += 1,
+                ShadowMatcherToken::Capture(_) => captures += 1,
+                // Only the parts that must be present in a route are counted.
+                ShadowMatcherToken::Optional(_) => {}
+                ShadowMatcherToken::Query { params, .. } => {
+                    for param in params.iter().filter(|param| !param.optional) {
+                        match &param.value {
+                            ShadowQueryValue::Exact(value) => {
+                                literal_length += param.key.chars().count() + value.chars().count()
+                            }
+                            ShadowQueryValue::Capture(capture) if capture.is_many() => {}
+                            ShadowQueryValue::Capture(_) => {
+                                literal_length += param.key.chars().count();
+                                captures += 1;
+                            }
+                        }
+                    }
+                }
+                ShadowMatcherToken::End => ends = true,
+            }
+        }
+        Specificity {
+            literal_length,
+            many_captures: Reverse(many_captures),
+            captures: Reverse(captures),
+            ends,
+        }
+    }
+}
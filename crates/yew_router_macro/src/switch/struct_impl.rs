@@ -1,4 +1,5 @@
 pub use self::{build_route_section::BuildRouteSection, from_route_part::FromRoutePart};
+use crate::switch::ItemMethods;
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
@@ -9,6 +10,7 @@ mod from_route_part;
 pub struct StructInner<'a> {
     pub from_route_part: FromRoutePart<'a>,
     pub build_route_section: BuildRouteSection<'a>,
+    pub item_methods: ItemMethods<'a>,
 }
 
 impl<'a> ToTokens for StructInner<'a> {
@@ -16,10 +18,12 @@ impl<'a> ToTokens for StructInner<'a> {
         let StructInner {
             from_route_part,
             build_route_section,
+            item_methods,
         } = self;
         tokens.extend(quote! {
              #from_route_part
              #build_route_section
+             #item_methods
         })
     }
 }
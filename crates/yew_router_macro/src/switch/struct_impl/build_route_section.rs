@@ -1,6 +1,4 @@
-use crate::switch::{
-    shadow::ShadowMatcherToken, unnamed_field_index_item, write_for_token, FieldType, SwitchItem,
-};
+use crate::switch::{unnamed_field_index_item, write_for_token, FieldType, SwitchItem};
 use proc_macro2::{Ident, TokenStream};
 use quote::{quote, ToTokens};
 use syn::Fields;
@@ -27,6 +25,7 @@ pub fn build_serializer_for_struct(switch_item: &SwitchItem, item: &Ident) -> To
         matcher,
         ident,
         fields,
+        ..
     } = switch_item;
     let destructor_and_writers = match fields {
         Fields::Named(fields_named) => {
@@ -50,9 +49,9 @@ pub fn build_serializer_for_struct(switch_item: &SwitchItem, item: &Ident) -> To
                 .map(|(index, _)| unnamed_field_index_item(index));
             let mut item_count = 0;
             let writers = matcher.iter().map(|token| {
-                if let ShadowMatcherToken::Capture(_) = &token {
+                if token.capture_count() > 0 {
                     let ts = write_for_token(token, FieldType::Unnamed { index: item_count });
-                    item_count += 1;
+                    item_count += token.capture_count();
                     ts
                 } else {
                     // Its either a literal, or something that will panic currently
@@ -1,33 +1,34 @@
 // use crate::switch::{SwitchItem, write_for_token, FieldType, unnamed_field_index_item};
-use crate::switch::SwitchItem;
+use crate::switch::{build_field_from_capture, SwitchItem};
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
-use syn::{Field, Fields, Type};
+use syn::{Field, Fields};
 
 
 pub struct FromRoutePart<'a>(pub &'a SwitchItem);
 
 impl<'a> ToTokens for FromRoutePart<'a> {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let SwitchItem {
-            matcher,
-            ident,
-            fields,
-        } = &self.0;
+        let SwitchItem { ident, fields, .. } = &self.0;
 
-        let matcher = super::super::build_matcher_from_tokens(&matcher);
+        let matcher = super::super::build_matcher_from_tokens(self.0);
         let build_from_captures = build_struct_from_captures(ident, fields);
 
+        // The matcher is built the first time it is used, rather than on every call.
         tokens.extend(quote! {
             fn from_route_part<__T>(
                 route: String, mut state: Option<__T>
             ) -> (::std::option::Option<Self>, ::std::option::Option<__T>) {
-                #matcher
+                ::std::thread_local! {
+                    static MATCHER: ::yew_router::matcher::RouteMatcher = #matcher;
+                }
                 let route_string = route;
 
-                #build_from_captures
+                MATCHER.with(move |matcher| {
+                    #build_from_captures
 
-                (::std::option::Option::None, state)
+                    (::std::option::Option::None, state)
+                })
             }
         })
     }
@@ -40,37 +41,18 @@ fn build_struct_from_captures(ident: &Ident, fields: &Fields) -> TokenStream {
                 .named
                 .iter()
                 .filter_map(|field: &Field| {
-                    let field_ty: &Type = &field.ty;
                     field.ident.as_ref().map(|i| {
                         let key = i.to_string();
-                        (i, key, field_ty)
+                        (i, key, field)
                     })
                 })
-                .map(|(field_name, key, field_ty): (&Ident, String, &Type)| {
+                .map(|(field_name, key, field): (&Ident, String, &Field)| {
+                    let field_value = build_field_from_capture(
+                        quote! {captures.remove(#key).map(::std::borrow::Cow::into_owned)},
+                        field,
+                    );
                     let field_decl = quote! {
-                        let #field_name = {
-                            let (v, s) = match captures.remove(#key) {
-                                ::std::option::Option::Some(value) => {
-                                    <#field_ty as ::yew_router::Switch>::from_route_part(
-                                        value,
-                                        state,
-                                    )
-                                }
-                                ::std::option::Option::None => {
-                                    (
-                                        <#field_ty as ::yew_router::Switch>::key_not_available(),
-                                        state,
-                                    )
-                                }
-                            };
-                            match v {
-                                ::std::option::Option::Some(val) => {
-                                    state = s; // Set state for the next var.
-                                    val
-                                },
-                                ::std::option::Option::None => return (::std::option::Option::None, s) // Failed
-                            }
-                        };
+                        let #field_name = #field_value;
                     };
 
                     (field_decl, field_name)
@@ -102,32 +84,15 @@ fn build_struct_from_captures(ident: &Ident, fields: &Fields) -> TokenStream {
                 .iter()
                 .enumerate()
                 .map(|(idx, f)| {
-                    let field_ty = &f.ty;
                     let field_var_name = Ident::new(&format!("field_{}", idx), Span::call_site());
+                    let field_value = build_field_from_capture(
+                        quote! {
+                            drain.next().and_then(|value| value).map(::std::borrow::Cow::into_owned)
+                        },
+                        f,
+                    );
                     let field_decl = quote! {
-                        let #field_var_name = {
-                            let (v, s) = match drain.next() {
-                                ::std::option::Option::Some(value) => {
-                                    <#field_ty as ::yew_router::Switch>::from_route_part(
-                                        value,
-                                        state,
-                                    )
-                                },
-                                ::std::option::Option::None => {
-                                    (
-                                        <#field_ty as ::yew_router::Switch>::key_not_available(),
-                                        state,
-                                    )
-                                }
-                            };
-                            match v {
-                                ::std::option::Option::Some(val) => {
-                                    state = s; // Set state for the next var.
-                                    val
-                                },
-                                ::std::option::Option::None => return (::std::option::Option::None, s) // Failed
-                            }
-                        };
+                        let #field_var_name = #field_value;
                     };
 
                     (field_decl, field_var_name)
@@ -135,7 +100,7 @@ fn build_struct_from_captures(ident: &Ident, fields: &Fields) -> TokenStream {
                 .unzip();
 
             quote! {
-                if let Some(mut captures) = matcher.capture_route_into_vec(&route_string).ok().map(|x| x.1) {
+                if let Some(mut captures) = matcher.capture_route_into_optional_vec(&route_string).ok().map(|x| x.1) {
                     let mut drain = captures.drain(..);
                     #(#field_declarations)*
 
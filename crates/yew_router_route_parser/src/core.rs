@@ -1,18 +1,18 @@
 use crate::{
     error::{ExpectedToken, ParserErrorReason},
-    parser::{CaptureOrExact, RefCaptureVariant, RouteParserToken},
+    parser::{CaptureOrExact, RefCaptureConstraint, RefCaptureVariant, RouteParserToken},
     ParseError,
 };
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_till1},
+    bytes::complete::{tag, take_till, take_till1},
     character::{
         complete::{char, digit1},
         is_digit,
     },
     combinator::{map, map_parser},
     error::ErrorKind,
-    sequence::{delimited, separated_pair},
+    sequence::{delimited, separated_pair, terminated},
     IResult,
 };
 
@@ -27,6 +27,100 @@ pub enum FieldNamingScheme {
     Unit,
 }
 
+/// Primitive types that can be used to constrain a capture section, like in `{id:u32}`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum PrimitiveType {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isize,
+    F32,
+    F64,
+    Bool,
+    Char,
+}
+
+impl PrimitiveType {
+    /// Gets the type corresponding to the name of a primitive.
+    pub fn from_name(name: &str) -> Option<Self> {
+        use PrimitiveType as PT;
+        Some(match name {
+            "u8" => PT::U8,
+            "u16" => PT::U16,
+            "u32" => PT::U32,
+            "u64" => PT::U64,
+            "u128" => PT::U128,
+            "usize" => PT::Usize,
+            "i8" => PT::I8,
+            "i16" => PT::I16,
+            "i32" => PT::I32,
+            "i64" => PT::I64,
+            "i128" => PT::I128,
+            "isize" => PT::Isize,
+            "f32" => PT::F32,
+            "f64" => PT::F64,
+            "bool" => PT::Bool,
+            "char" => PT::Char,
+            _ => return None,
+        })
+    }
+
+    /// The name of the primitive, as it would be written in Rust.
+    pub fn name(self) -> &'static str {
+        use PrimitiveType as PT;
+        match self {
+            PT::U8 => "u8",
+            PT::U16 => "u16",
+            PT::U32 => "u32",
+            PT::U64 => "u64",
+            PT::U128 => "u128",
+            PT::Usize => "usize",
+            PT::I8 => "i8",
+            PT::I16 => "i16",
+            PT::I32 => "i32",
+            PT::I64 => "i64",
+            PT::I128 => "i128",
+            PT::Isize => "isize",
+            PT::F32 => "f32",
+            PT::F64 => "f64",
+            PT::Bool => "bool",
+            PT::Char => "char",
+        }
+    }
+
+    /// Determines if the string can be parsed as the primitive.
+    pub fn can_parse(self, s: &str) -> bool {
+        use PrimitiveType as PT;
+        match self {
+            PT::U8 => s.parse::<u8>().is_ok(),
+            PT::U16 => s.parse::<u16>().is_ok(),
+            PT::U32 => s.parse::<u32>().is_ok(),
+            PT::U64 => s.parse::<u64>().is_ok(),
+            PT::U128 => s.parse::<u128>().is_ok(),
+            PT::Usize => s.parse::<usize>().is_ok(),
+            PT::I8 => s.parse::<i8>().is_ok(),
+            PT::I16 => s.parse::<i16>().is_ok(),
+            PT::I32 => s.parse::<i32>().is_ok(),
+            PT::I64 => s.parse::<i64>().is_ok(),
+            PT::I128 => s.parse::<i128>().is_ok(),
+            PT::Isize => s.parse::<isize>().is_ok(),
+            PT::F32 => s.parse::<f32>().is_ok(),
+            PT::F64 => s.parse::<f64>().is_ok(),
+            PT::Bool => s.parse::<bool>().is_ok(),
+            PT::Char => s.parse::<char>().is_ok(),
+        }
+    }
+}
+
 pub fn get_slash(i: &str) -> IResult<&str, RouteParserToken, ParseError> {
     map(char('/'), |_: char| RouteParserToken::Separator)(i)
         .map_err(|_: nom::Err<()>| nom::Err::Error(ParseError::expected(ExpectedToken::Separator)))
@@ -56,6 +150,20 @@ pub fn get_end(i: &str) -> IResult<&str, RouteParserToken, ParseError> {
         .map_err(|_: nom::Err<()>| nom::Err::Error(ParseError::expected(ExpectedToken::End)))
 }
 
+/// Returns an OptionalBegin variant if the next character is a '['.
+pub fn get_open_optional(i: &str) -> IResult<&str, RouteParserToken, ParseError> {
+    map(char('['), |_: char| RouteParserToken::OptionalBegin)(i).map_err(|_: nom::Err<()>| {
+        nom::Err::Error(ParseError::expected(ExpectedToken::OpenOptional))
+    })
+}
+
+/// Returns an OptionalEnd variant if the next character is a ']'.
+pub fn get_close_optional(i: &str) -> IResult<&str, RouteParserToken, ParseError> {
+    map(char(']'), |_: char| RouteParserToken::OptionalEnd)(i).map_err(|_: nom::Err<()>| {
+        nom::Err::Error(ParseError::expected(ExpectedToken::CloseOptional))
+    })
+}
+
 /// Returns an End variant if the next character is a '!`.
 fn get_open_bracket(i: &str) -> IResult<&str, (), ParseError> {
     map(char('{'), |_: char| ())(i).map_err(|_: nom::Err<()>| {
@@ -74,6 +182,11 @@ fn get_eq(i: &str) -> IResult<&str, (), ParseError> {
         .map_err(|_: nom::Err<()>| nom::Err::Error(ParseError::expected(ExpectedToken::Equals)))
 }
 
+fn get_question_mark(i: &str) -> IResult<&str, (), ParseError> {
+    map(char('?'), |_: char| ())(i)
+        .map_err(|_: nom::Err<()>| nom::Err::Error(ParseError::expected(ExpectedToken::Question)))
+}
+
 fn get_star(i: &str) -> IResult<&str, (), ParseError> {
     map(char('*'), |_: char| ())(i)
         .map_err(|_: nom::Err<()>| nom::Err::Error(ParseError::expected(ExpectedToken::Star)))
@@ -85,10 +198,17 @@ fn get_colon(i: &str) -> IResult<&str, (), ParseError> {
 }
 
 fn rust_ident(i: &str) -> IResult<&str, &str, ParseError> {
+    rust_ident_until("}")(i)
+}
+
+/// Reads an ident up until one of the terminating characters is found.
+fn rust_ident_until<'a>(
+    terminators: &'static str,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, ParseError> {
     let invalid_ident_chars = r##" \|/{[]()?+=-!@#$%^&*~`'";:"##;
-    // Detect an ident by first reading until a } is found,
+    // Detect an ident by first reading until a terminator is found,
     // then validating the captured section against invalid characters that can't be in rust idents.
-    map_parser(take_till1(move |c| c == '}'), move |i: &str| {
+    map_parser(take_till1(move |c| terminators.contains(c)), move |i: &str| {
         match take_till1::<_, _, ()>(|c| invalid_ident_chars.contains(c))(i) {
             Ok((remain, got)) => {
                 // Detects if the first character is a digit.
@@ -110,19 +230,39 @@ fn rust_ident(i: &str) -> IResult<&str, &str, ParseError> {
                     Ok((i, i))
                 }
             }
-            Err(_) => Ok((i, i)),
+            // The first character is already invalid.
+            Err(_) => Err(nom::Err::Failure(ParseError {
+                reason: Some(ParserErrorReason::BadRustIdent(i.chars().next().unwrap())),
+                expected: vec![ExpectedToken::Ident],
+                offset: 1,
+            })),
         }
-    })(i)
+    })
 }
 
 /// Matches escaped items
 fn escaped_item_impl(i: &str) -> IResult<&str, &str> {
-    map(alt((tag("!!"), tag("{{"), tag("}}"))), |s| match s {
-        "!!" => "!",
-        "}}" => "}",
-        "{{" => "{",
-        _ => unreachable!(),
-    })(i)
+    map(
+        alt((
+            tag("!!"),
+            tag("{{"),
+            tag("}}"),
+            tag("[["),
+            tag("]]"),
+            tag("(("),
+            tag("))"),
+        )),
+        |s| match s {
+            "!!" => "!",
+            "}}" => "}",
+            "{{" => "{",
+            "[[" => "[",
+            "]]" => "]",
+            "((" => "(",
+            "))" => ")",
+            _ => unreachable!(),
+        },
+    )(i)
 }
 
 /// Matches "".
@@ -166,10 +306,47 @@ fn exact_impl(special_chars: &'static str) -> impl Fn(&str) -> IResult<&str, &st
 }
 
 const SPECIAL_CHARS: &str = r##"/?&#={}!"##;
+const PATH_SPECIAL_CHARS: &str = r##"/?&#={}![]()"##;
+/// Characters that terminate the alternatives of a `OneOf`, apart from the `|` separating them.
+const ALTERNATIVE_SPECIAL_CHARS: &str = r##"?&#={}![]()"##;
 const FRAGMENT_SPECIAL_CHARS: &str = r##"{}!"##;
 
 pub fn exact(i: &str) -> IResult<&str, RouteParserToken, ParseError> {
-    map(exact_impl(SPECIAL_CHARS), RouteParserToken::Exact)(i)
+    map(exact_impl(PATH_SPECIAL_CHARS), RouteParserToken::Exact)(i)
+}
+
+/// Matches a set of alternative literals separated by `|` within parenthesis, like `(users|members)`.
+///
+/// Once the opening `(` has been found, any problem with the alternatives is a failure.
+pub fn one_of(i: &str) -> IResult<&str, RouteParserToken, ParseError> {
+    let (ii, _) = get_open_paren(i)?;
+    let (ii, alternatives) = take_till(|c| ALTERNATIVE_SPECIAL_CHARS.contains(c))(ii)?;
+    let (ii, _) = get_close_paren(ii).map_err(|_| {
+        nom::Err::Failure(ParseError {
+            reason: None,
+            expected: vec![ExpectedToken::Literal, ExpectedToken::CloseParen],
+            offset: 1 + alternatives.len(),
+        })
+    })?;
+    if alternatives.split('|').any(str::is_empty) {
+        return Err(nom::Err::Failure(ParseError {
+            reason: Some(ParserErrorReason::EmptyAlternative),
+            expected: vec![ExpectedToken::Literal],
+            offset: 1,
+        }));
+    }
+    Ok((ii, RouteParserToken::OneOf(alternatives)))
+}
+
+fn get_open_paren(i: &str) -> IResult<&str, (), ParseError> {
+    map(char('('), |_: char| ())(i)
+        .map_err(|_: nom::Err<()>| nom::Err::Error(ParseError::expected(ExpectedToken::OpenParen)))
+}
+
+fn get_close_paren(i: &str) -> IResult<&str, (), ParseError> {
+    map(char(')'), |_: char| ())(i).map_err(|_: nom::Err<()>| {
+        nom::Err::Error(ParseError::expected(ExpectedToken::CloseParen))
+    })
 }
 
 /// More permissive exact matchers
@@ -191,12 +368,19 @@ fn capture_single_impl<'a>(
     move |i: &str| match field_naming_scheme {
         FieldNamingScheme::Named => delimited(
             get_open_bracket,
-            named::single_capture_impl,
+            alt((
+                named::constrained_capture_impl,
+                named::single_capture_impl,
+            )),
             get_close_bracket,
         )(i),
         FieldNamingScheme::Unnamed => delimited(
             get_open_bracket,
-            alt((named::single_capture_impl, unnamed::single_capture_impl)),
+            alt((
+                named::constrained_capture_impl,
+                named::single_capture_impl,
+                unnamed::single_capture_impl,
+            )),
             get_close_bracket,
         )(i),
         FieldNamingScheme::Unit => {
@@ -210,7 +394,7 @@ fn capture_single_impl<'a>(
     }
 }
 
-/// Captures {ident}, {*:ident}, {<number>:ident}
+/// Captures {ident}, {*:ident}, {<number>:ident}, {ident:constraint}
 ///
 /// Depending on the provided field naming, it may also match {}, {*}, and {<number>} for unnamed fields, or none at all for units.
 fn capture_impl<'a>(
@@ -221,6 +405,7 @@ fn capture_impl<'a>(
             let inner = alt((
                 named::many_capture_impl,
                 named::numbered_capture_impl,
+                named::constrained_capture_impl,
                 named::single_capture_impl,
             ));
             delimited(get_open_bracket, inner, get_close_bracket)(i)
@@ -231,6 +416,7 @@ fn capture_impl<'a>(
                 unnamed::many_capture_impl,
                 named::numbered_capture_impl,
                 unnamed::numbered_capture_impl,
+                named::constrained_capture_impl,
                 named::single_capture_impl,
                 unnamed::single_capture_impl,
             ));
@@ -266,6 +452,70 @@ mod named {
             },
         )(i)
     }
+
+    /// Matches `ident:constraint`.
+    ///
+    /// If no colon follows the ident, this fails with a recoverable error,
+    /// so `single_capture_impl` can be tried afterwards.
+    pub fn constrained_capture_impl(i: &str) -> IResult<&str, RefCaptureVariant, ParseError> {
+        let (ii, key) = rust_ident_until(":}")(i)?;
+        let (ii, _) = get_colon(ii)?;
+        let (ii, constraint) = constraint(ii).map_err(|e| match e {
+            nom::Err::Failure(mut e) => {
+                // Point past the ident and the colon.
+                e.offset += key.len() + 1;
+                nom::Err::Failure(e)
+            }
+            e => e,
+        })?;
+        Ok((ii, RefCaptureVariant::ConstrainedNamed {
+            name: key,
+            constraint,
+        }))
+    }
+}
+
+/// Matches either a character class like `[a-z]+` or the name of a primitive type.
+fn constraint(i: &str) -> IResult<&str, RefCaptureConstraint, ParseError> {
+    if i.starts_with('[') {
+        let (ii, class) = delimited(char('['), take_till1(|c| c == ']' || c == '}'), char(']'))(i)
+            .map_err(|_: nom::Err<()>| {
+                nom::Err::Failure(ParseError {
+                    reason: Some(ParserErrorReason::BadConstraint),
+                    expected: vec![ExpectedToken::Constraint],
+                    offset: 1,
+                })
+            })?;
+        // A class without a quantifier would read as matching a single character.
+        let (ii, quantifier) = alt((char('+'), char('*')))(ii).map_err(|_: nom::Err<()>| {
+            nom::Err::Failure(ParseError {
+                reason: Some(ParserErrorReason::BadConstraint),
+                expected: vec![ExpectedToken::Constraint],
+                offset: class.len() + 2,
+            })
+        })?;
+        Ok((ii, RefCaptureConstraint::CharacterClass {
+            class,
+            allow_empty: quantifier == '*',
+        }))
+    } else {
+        let (ii, name) = take_till1(|c| c == '}')(i)
+            .map_err(|_: nom::Err<()>| {
+                nom::Err::Failure(ParseError {
+                    reason: Some(ParserErrorReason::BadConstraint),
+                    expected: vec![ExpectedToken::Constraint],
+                    offset: 1,
+                })
+            })?;
+        match PrimitiveType::from_name(name) {
+            Some(primitive) => Ok((ii, RefCaptureConstraint::Primitive(primitive))),
+            None => Err(nom::Err::Failure(ParseError {
+                reason: Some(ParserErrorReason::BadConstraint),
+                expected: vec![ExpectedToken::Constraint],
+                offset: 1,
+            })),
+        }
+    }
 }
 
 mod unnamed {
@@ -294,6 +544,14 @@ fn cap_or_exact<'a>(
 ) -> impl Fn(&'a str) -> IResult<&'a str, CaptureOrExact<'a>, ParseError> {
     move |i: &str| {
         alt((
+            map(
+                optional_capture_impl(field_naming_scheme),
+                CaptureOrExact::OptionalCapture,
+            ),
+            map(
+                repeated_capture_impl(field_naming_scheme),
+                CaptureOrExact::Capture,
+            ),
             map(
                 capture_single_impl(field_naming_scheme),
                 CaptureOrExact::Capture,
@@ -303,6 +561,63 @@ fn cap_or_exact<'a>(
     }
 }
 
+/// Captures {*:ident}, and {*} for unnamed fields.
+///
+/// Within a query, these capture every value of a repeated key.
+fn repeated_capture_impl<'a>(
+    field_naming_scheme: FieldNamingScheme,
+) -> impl Fn(&'a str) -> IResult<&'a str, RefCaptureVariant<'a>, ParseError> {
+    move |i: &str| match field_naming_scheme {
+        FieldNamingScheme::Named => {
+            delimited(get_open_bracket, named::many_capture_impl, get_close_bracket)(i)
+        }
+        FieldNamingScheme::Unnamed => delimited(
+            get_open_bracket,
+            alt((named::many_capture_impl, unnamed::many_capture_impl)),
+            get_close_bracket,
+        )(i),
+        FieldNamingScheme::Unit => Err(nom::Err::Failure(ParseError {
+            reason: Some(ParserErrorReason::CapturesInUnit),
+            expected: vec![],
+            offset: 0,
+        })),
+    }
+}
+
+/// Captures {ident?}, and {?} for unnamed fields.
+///
+/// If the capture doesn't end with a `?`, this fails with a recoverable error,
+/// so the other capture forms can be tried afterwards.
+fn optional_capture_impl<'a>(
+    field_naming_scheme: FieldNamingScheme,
+) -> impl Fn(&'a str) -> IResult<&'a str, RefCaptureVariant<'a>, ParseError> {
+    move |i: &str| {
+        let (_, inner) = delimited(get_open_bracket, take_till(|c| c == '}'), get_close_bracket)(i)?;
+        if !inner.ends_with('?') {
+            return Err(nom::Err::Error(ParseError::expected(ExpectedToken::Question)));
+        }
+        let named = map(terminated(rust_ident_until("?"), get_question_mark), |key| {
+            RefCaptureVariant::Named(key)
+        });
+        match field_naming_scheme {
+            FieldNamingScheme::Named => delimited(get_open_bracket, named, get_close_bracket)(i),
+            FieldNamingScheme::Unnamed => delimited(
+                get_open_bracket,
+                alt((
+                    named,
+                    map(get_question_mark, |_| RefCaptureVariant::Unnamed),
+                )),
+                get_close_bracket,
+            )(i),
+            FieldNamingScheme::Unit => Err(nom::Err::Failure(ParseError {
+                reason: Some(ParserErrorReason::CapturesInUnit),
+                expected: vec![],
+                offset: 0,
+            })),
+        }
+    }
+}
+
 /// Matches a query
 pub fn query<'a>(
     field_naming_scheme: FieldNamingScheme,
@@ -332,6 +647,33 @@ mod test {
         assert_eq!(x.1, RouteParserToken::Exact("hello"))
     }
 
+    #[test]
+    fn one_of_alternatives() {
+        let (_, token) = one_of("(users|members)").expect("Should parse");
+        assert_eq!(token, RouteParserToken::OneOf("users|members"))
+    }
+
+    #[test]
+    fn one_of_unclosed_fails() {
+        one_of("(users|members").expect_err("Should not parse");
+    }
+
+    #[test]
+    fn one_of_empty_alternative_fails() {
+        match one_of("(users|)") {
+            Err(nom::Err::Failure(e)) => {
+                assert_eq!(e.reason, Some(ParserErrorReason::EmptyAlternative))
+            }
+            _ => panic!("Should fail"),
+        }
+    }
+
+    #[test]
+    fn escaped_paren_is_literal() {
+        let (_, token) = exact("((").expect("Should parse");
+        assert_eq!(token, RouteParserToken::Exact("("))
+    }
+
     #[test]
     fn cap_or_exact_match_lit() {
         cap_or_exact(FieldNamingScheme::Named)("lorem").expect("Should parse");
@@ -359,6 +701,56 @@ mod test {
         query(FieldNamingScheme::Unnamed)("lorem={}").expect("should parse");
     }
 
+    #[test]
+    fn query_section_optional_capture_named() {
+        let (_, token) = query(FieldNamingScheme::Named)("page={page?}").expect("should parse");
+        assert_eq!(
+            token,
+            RouteParserToken::Query {
+                ident: "page",
+                capture_or_exact: CaptureOrExact::OptionalCapture(RefCaptureVariant::Named("page"))
+            }
+        );
+    }
+    #[test]
+    fn query_section_optional_capture_unnamed() {
+        let (_, token) = query(FieldNamingScheme::Unnamed)("page={?}").expect("should parse");
+        assert_eq!(
+            token,
+            RouteParserToken::Query {
+                ident: "page",
+                capture_or_exact: CaptureOrExact::OptionalCapture(RefCaptureVariant::Unnamed)
+            }
+        );
+    }
+    #[test]
+    fn query_section_optional_capture_named_fails_without_key() {
+        query(FieldNamingScheme::Named)("page={?}").expect_err("should not parse");
+    }
+
+    #[test]
+    fn query_section_repeated_capture_named() {
+        let (_, token) = query(FieldNamingScheme::Named)("tag={*:tags}").expect("should parse");
+        assert_eq!(
+            token,
+            RouteParserToken::Query {
+                ident: "tag",
+                capture_or_exact: CaptureOrExact::Capture(RefCaptureVariant::ManyNamed("tags"))
+            }
+        );
+    }
+    #[test]
+    fn query_section_repeated_capture_unnamed() {
+        let (_, token) = query(FieldNamingScheme::Unnamed)("tag={*}").expect("should parse");
+        assert_eq!(
+            token,
+            RouteParserToken::Query {
+                ident: "tag",
+                capture_or_exact: CaptureOrExact::Capture(RefCaptureVariant::ManyUnnamed)
+            }
+        );
+    }
+
     #[test]
     fn non_leading_numbers_in_ident() {
         rust_ident("hello5").expect("sholud parse");
@@ -367,4 +759,54 @@ mod test {
     fn leading_numbers_in_ident_fails() {
         rust_ident("5hello").expect_err("sholud not parse");
     }
+
+    #[test]
+    fn capture_primitive_constraint() {
+        let x = capture(FieldNamingScheme::Named)("{id:u32}").expect("Should parse");
+        assert_eq!(
+            x.1,
+            RouteParserToken::Capture(RefCaptureVariant::ConstrainedNamed {
+                name: "id",
+                constraint: RefCaptureConstraint::Primitive(PrimitiveType::U32)
+            })
+        )
+    }
+
+    #[test]
+    fn capture_class_constraint() {
+        let x = capture(FieldNamingScheme::Named)("{slug:[a-z-]+}").expect("Should parse");
+        assert_eq!(
+            x.1,
+            RouteParserToken::Capture(RefCaptureVariant::ConstrainedNamed {
+                name: "slug",
+                constraint: RefCaptureConstraint::CharacterClass {
+                    class: "a-z-",
+                    allow_empty: false
+                }
+            })
+        )
+    }
+
+    #[test]
+    fn capture_class_without_quantifier_fails() {
+        let e = capture(FieldNamingScheme::Named)("{s:[a-z]}").expect_err("Should not parse");
+        match e {
+            nom::Err::Failure(e) => assert_eq!(e.reason, Some(ParserErrorReason::BadConstraint)),
+            _ => panic!("Should be a failure"),
+        }
+    }
+
+    #[test]
+    fn capture_unknown_constraint_fails() {
+        let e = capture(FieldNamingScheme::Named)("{id:u33}").expect_err("Should not parse");
+        match e {
+            nom::Err::Failure(e) => assert_eq!(e.reason, Some(ParserErrorReason::BadConstraint)),
+            _ => panic!("Should be a failure"),
+        }
+    }
+
+    #[test]
+    fn query_section_capture_constrained() {
+        query(FieldNamingScheme::Named)("lorem={ipsum:i64}").expect("should parse");
+    }
 }
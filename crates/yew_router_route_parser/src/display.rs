@@ -0,0 +1,177 @@
+//! Renders matcher tokens back into the matcher string syntax.
+use crate::{CaptureConstraint, CaptureVariant, MatcherToken, QueryParam, QueryValue};
+use std::fmt::{self, Display, Formatter, Write};
+
+/// Characters that must be escaped by doubling them within a literal in the path.
+const PATH_ESCAPED_CHARS: &str = "!{}[]()";
+/// Characters that must be escaped by doubling them within a fragment literal.
+const FRAGMENT_ESCAPED_CHARS: &str = "!{}";
+
+/// Displays a sequence of tokens as the matcher string they were parsed from.
+///
+/// Unlike displaying each token separately, this keeps track of whether a fragment has started,
+/// after which brackets and parenthesis are no longer escaped.
+///
+/// # Example
+/// ```
+/// use yew_router_route_parser::{parse_str_and_optimize_tokens, DisplayTokens, FieldNamingScheme};
+/// let tokens =
+///     parse_str_and_optimize_tokens("/a/{b}?c={d}", FieldNamingScheme::Named).unwrap();
+/// assert_eq!(DisplayTokens(&tokens).to_string(), "/a/{b}?c={d}");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayTokens<'a>(pub &'a [MatcherToken]);
+
+impl<'a> Display for DisplayTokens<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut in_fragment = false;
+        self.0
+            .iter()
+            .try_for_each(|token| fmt_token(token, f, &mut in_fragment))
+    }
+}
+
+impl Display for MatcherToken {
+    /// Renders the token as it would appear in a matcher string within a path.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        fmt_token(self, f, &mut false)
+    }
+}
+
+fn fmt_token(token: &MatcherToken, f: &mut Formatter, in_fragment: &mut bool) -> fmt::Result {
+    match token {
+        MatcherToken::Exact(literal) => {
+            for c in literal.chars() {
+                *in_fragment |= c == '#';
+                let escaped = if *in_fragment {
+                    FRAGMENT_ESCAPED_CHARS
+                } else {
+                    PATH_ESCAPED_CHARS
+                };
+                write_char_escaped(f, c, escaped)?;
+            }
+            Ok(())
+        }
+        MatcherToken::Capture(capture) => write!(f, "{}", capture),
+        MatcherToken::OneOf(alternatives) => write!(f, "({})", alternatives.join("|")),
+        MatcherToken::Optional(group) => write!(f, "[{}]", DisplayTokens(group)),
+        MatcherToken::Query { continued, params } => {
+            f.write_char(if *continued { '&' } else { '?' })?;
+            params.iter().enumerate().try_for_each(|(index, param)| {
+                if index > 0 {
+                    f.write_char('&')?;
+                }
+                write!(f, "{}", param)
+            })
+        }
+        MatcherToken::End => f.write_char('!'),
+    }
+}
+
+fn write_char_escaped(f: &mut Formatter, c: char, escaped: &str) -> fmt::Result {
+    if escaped.contains(c) {
+        f.write_char(c)?;
+    }
+    f.write_char(c)
+}
+
+impl Display for QueryParam {
+    /// Renders the parameter as `key=value`.
+    ///
+    /// Keys and values are written as they are, because the parser doesn't accept escaped
+    /// characters within a query.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}=", self.key)?;
+        match &self.value {
+            QueryValue::Exact(value) => f.write_str(value),
+            QueryValue::Capture(CaptureVariant::Named(name)) if self.optional => {
+                write!(f, "{{{}?}}", name)
+            }
+            QueryValue::Capture(CaptureVariant::Unnamed) if self.optional => f.write_str("{?}"),
+            QueryValue::Capture(capture) => write!(f, "{}", capture),
+        }
+    }
+}
+
+impl Display for CaptureVariant {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            CaptureVariant::Unnamed => f.write_str("{}"),
+            CaptureVariant::ManyUnnamed => f.write_str("{*}"),
+            CaptureVariant::NumberedUnnamed { sections } => write!(f, "{{{}}}", sections),
+            CaptureVariant::Named(name) => write!(f, "{{{}}}", name),
+            CaptureVariant::ManyNamed(name) => write!(f, "{{*:{}}}", name),
+            CaptureVariant::NumberedNamed { sections, name } => {
+                write!(f, "{{{}:{}}}", sections, name)
+            }
+            CaptureVariant::ConstrainedNamed { name, constraint } => {
+                write!(f, "{{{}:{}}}", name, constraint)
+            }
+        }
+    }
+}
+
+impl Display for CaptureConstraint {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            CaptureConstraint::Primitive(primitive) => f.write_str(primitive.name()),
+            CaptureConstraint::CharacterClass { class, allow_empty } => {
+                write!(f, "[{}]{}", class, if *allow_empty { '*' } else { '+' })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_str_and_optimize_tokens, FieldNamingScheme};
+
+    fn round_trip(i: &str, field_naming_scheme: FieldNamingScheme) {
+        let tokens = parse_str_and_optimize_tokens(i, field_naming_scheme).expect("Should parse");
+        let displayed = DisplayTokens(&tokens).to_string();
+        assert_eq!(displayed, i);
+        let reparsed =
+            parse_str_and_optimize_tokens(&displayed, field_naming_scheme).expect("Should reparse");
+        assert_eq!(reparsed, tokens);
+    }
+
+    #[test]
+    fn path_round_trips() {
+        round_trip("/a/{b}/{*:c}/{2:d}!", FieldNamingScheme::Named);
+        round_trip("/a/{}/{*}/{3}", FieldNamingScheme::Unnamed);
+    }
+
+    #[test]
+    fn constraints_round_trip() {
+        round_trip("/{id:u32}/{slug:[a-z-]+}/{rest:[^/]*}", FieldNamingScheme::Named);
+    }
+
+    #[test]
+    fn groups_and_alternatives_round_trip() {
+        round_trip("/(users|members)/{id}[/page/{page}]", FieldNamingScheme::Named);
+    }
+
+    #[test]
+    fn query_round_trips() {
+        round_trip("/a?b={b}&c=d&e={e?}&f={*:f}#frag", FieldNamingScheme::Named);
+        round_trip("&b={?}&c={*}", FieldNamingScheme::Unnamed);
+    }
+
+    #[test]
+    fn escaped_literals_round_trip() {
+        round_trip("/!!/{{a}}/[[b]]/((c))!!", FieldNamingScheme::Unit);
+        round_trip("/a#{{b}}[c](d)!!", FieldNamingScheme::Unit);
+    }
+
+    #[test]
+    fn fragment_is_tracked_across_tokens() {
+        round_trip("#{a}[b]", FieldNamingScheme::Named);
+    }
+
+    #[test]
+    fn single_token_is_escaped() {
+        let token = MatcherToken::Exact("/{a}!".to_string());
+        assert_eq!(token.to_string(), "/{{a}}!!");
+    }
+}
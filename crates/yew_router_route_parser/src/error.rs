@@ -17,6 +17,13 @@ fn offset(input: &str, substring: &str) -> usize {
     input.len() - substring.len()
 }
 
+impl<'a> PrettyParseError<'a> {
+    /// The byte offset into the input at which the error was encountered.
+    pub fn position(&self) -> usize {
+        offset(self.input, self.remaining) + self.error.offset
+    }
+}
+
 impl<'a> fmt::Debug for PrettyParseError<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("Could not parse route.")?;
@@ -27,9 +34,7 @@ impl<'a> fmt::Debug for PrettyParseError<'a> {
         f.write_str(self.input)?;
         f.write_str("\n")?;
 
-        let offset = offset(self.input, self.remaining);
-        let offset = offset + self.error.offset;
-        let pad = (0..offset + route_str.len())
+        let pad = (0..self.position() + route_str.len())
             .map(|_| '-')
             .collect::<String>();
         f.write_str(&format!("{}^", pad))?;
@@ -134,6 +139,18 @@ pub enum ExpectedToken {
     Star,
     /// :
     Colon,
+    /// ? at the end of an optional capture
+    Question,
+    /// type name or character class following a `:`
+    Constraint,
+    /// [
+    OpenOptional,
+    /// ]
+    CloseOptional,
+    /// (
+    OpenParen,
+    /// )
+    CloseParen,
 }
 
 impl fmt::Display for ExpectedToken {
@@ -151,6 +168,12 @@ impl fmt::Display for ExpectedToken {
             ExpectedToken::Equals => f.write_str("="),
             ExpectedToken::Star => f.write_str("*"),
             ExpectedToken::Colon => f.write_str(":"),
+            ExpectedToken::Question => f.write_str("?"),
+            ExpectedToken::Constraint => f.write_str("<constraint>"),
+            ExpectedToken::OpenOptional => f.write_str("["),
+            ExpectedToken::CloseOptional => f.write_str("]"),
+            ExpectedToken::OpenParen => f.write_str("("),
+            ExpectedToken::CloseParen => f.write_str(")"),
         }
     }
 }
@@ -178,6 +201,16 @@ pub enum ParserErrorReason {
     InvalidState,
     /// Can't have capture sections for unit structs/variants
     CapturesInUnit,
+    /// The constraint within a capture group is neither a primitive type nor a character class.
+    BadConstraint,
+    /// An optional group was opened within another optional group.
+    NestedOptional,
+    /// An optional group was not closed before the path ended.
+    UnclosedOptional,
+    /// A `]` appeared without a corresponding `[`.
+    UnopenedOptional,
+    /// One of the alternatives within `(...|...)` is empty.
+    EmptyAlternative,
     /// Internal check on valid state transitions
     /// This should never actually be created.
     NotAllowedStateTransition,
@@ -227,6 +260,21 @@ impl fmt::Display for ParserErrorReason {
             ParserErrorReason::CapturesInUnit => {
                 f.write_str("Cannot have a capture section for a unit struct or variant.")?;
             }
+            ParserErrorReason::NestedOptional => {
+                f.write_str("Optional groups ([]) can't be nested.")?;
+            }
+            ParserErrorReason::UnclosedOptional => {
+                f.write_str("Optional groups ([]) must be closed before the end of the path.")?;
+            }
+            ParserErrorReason::UnopenedOptional => {
+                f.write_str("A ']' must close an optional group opened with a '['.")?;
+            }
+            ParserErrorReason::EmptyAlternative => {
+                f.write_str("Alternatives within (|) can't be empty.")?;
+            }
+            ParserErrorReason::BadConstraint => {
+                f.write_str("Constraints must be a primitive type (`u32`) or a character class followed by `+` or `*` (`[a-z]+`).")?;
+            }
         }
         Ok(())
     }
@@ -14,19 +14,22 @@
 )]
 
 mod core;
+mod display;
 mod error;
 pub mod parser;
-pub use crate::core::FieldNamingScheme;
+pub use crate::core::{FieldNamingScheme, PrimitiveType};
+pub use display::DisplayTokens;
 pub use error::{ParseError, PrettyParseError};
 mod optimizer;
 pub use optimizer::{convert_tokens, parse_str_and_optimize_tokens};
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap};
 
-/// Alias of `HashMap<&'a str, String>` that represent strings captured from a route.
+/// Alias of `HashMap<&'a str, Cow<'a, str>>` that represent strings captured from a route.
 ///
 /// Captures contain keys corresponding to named match sections,
 /// and values containing the content captured by those sections.
-pub type Captures<'a> = HashMap<&'a str, String>;
+/// Values borrow from the route unless they had to be decoded.
+pub type Captures<'a> = HashMap<&'a str, Cow<'a, str>>;
 
 /// Tokens used to determine how to match and capture sections from a URL.
 #[derive(Debug, PartialEq, Clone)]
@@ -35,12 +38,85 @@ pub enum MatcherToken {
     Exact(String),
     /// Capture section.
     Capture(CaptureVariant),
+    /// (...|...) alternative literals, any one of which will match.
+    ///
+    /// The first alternative is used when building a route.
+    OneOf(Vec<String>),
+    /// [...] optional group.
+    ///
+    /// The contained tokens will either all match, or the group will be skipped entirely.
+    Optional(Vec<MatcherToken>),
+    /// ?key=value&key={capture}&key={capture?} query section.
+    ///
+    /// The parameters are matched as a set, so they can appear in any order,
+    /// and parameters that aren't specified are ignored.
+    Query {
+        /// The section begins with a `&` instead of a `?`,
+        /// continuing a query that was started elsewhere.
+        continued: bool,
+        /// Parameters that must be present in the query, unless they are optional.
+        params: Vec<QueryParam>,
+    },
     /// End token - if the string hasn't been consumed entirely, then the parse will fail.
     /// This is useful for being able to specify more general matchers for variants that would
     /// otherwise match above more specific variants.
     End,
 }
 
+impl MatcherToken {
+    /// Is this token a capture section.
+    pub fn is_capture(&self) -> bool {
+        matches!(self, MatcherToken::Capture(_))
+    }
+}
+
+/// A single key-value pair within a query section.
+#[derive(Debug, PartialEq, Clone)]
+pub struct QueryParam {
+    /// The key of the parameter.
+    pub key: String,
+    /// What the value of the parameter must be.
+    pub value: QueryValue,
+    /// The parameter may be absent from the query, like `page={page?}`.
+    ///
+    /// Only captures can be optional.
+    pub optional: bool,
+}
+
+impl QueryParam {
+    /// Can the parameter be absent from the query.
+    ///
+    /// This is the case for optional parameters, and for many captures of repeated keys.
+    pub fn may_be_absent(&self) -> bool {
+        match &self.value {
+            QueryValue::Capture(CaptureVariant::ManyNamed(_))
+            | QueryValue::Capture(CaptureVariant::ManyUnnamed) => true,
+            _ => self.optional,
+        }
+    }
+}
+
+/// The value of a query parameter.
+#[derive(Debug, PartialEq, Clone)]
+pub enum QueryValue {
+    /// The value must match exactly.
+    Exact(String),
+    /// The value is captured.
+    ///
+    /// Many captures (`{*}`) collect every value of a repeated key, joined by `&`.
+    Capture(CaptureVariant),
+}
+
+impl QueryValue {
+    /// Is this value captured.
+    pub fn is_capture(&self) -> bool {
+        match self {
+            QueryValue::Capture(_) => true,
+            QueryValue::Exact(_) => false,
+        }
+    }
+}
+
 /// Variants that indicate how part of a string should be captured.
 #[derive(Debug, PartialEq, Clone)]
 pub enum CaptureVariant {
@@ -64,4 +140,79 @@ pub enum CaptureVariant {
         /// The key to be entered in the `Matches` map.
         name: String,
     },
+    /// {name:constraint} - captures a section if it satisfies the constraint and adds it to the
+    /// map with a given name.
+    ConstrainedNamed {
+        /// The key to be entered in the `Matches` map.
+        name: String,
+        /// The constraint the captured section must satisfy.
+        constraint: CaptureConstraint,
+    },
+}
+
+/// Restricts what content a capture section is allowed to match.
+#[derive(Debug, PartialEq, Clone)]
+pub enum CaptureConstraint {
+    /// {name:u32} - the section must be parsable as the given primitive type.
+    Primitive(PrimitiveType),
+    /// {name:[a-z-]+} - every character in the section must be contained in the character class.
+    CharacterClass {
+        /// The contents of the class, found between the `[` and `]`.
+        class: String,
+        /// Set if the class was followed by a `*` instead of a `+`, allowing empty sections.
+        allow_empty: bool,
+    },
+}
+
+impl CaptureConstraint {
+    /// Determines if a captured section satisfies the constraint.
+    pub fn is_satisfied_by(&self, section: &str) -> bool {
+        match self {
+            CaptureConstraint::Primitive(primitive) => primitive.can_parse(section),
+            CaptureConstraint::CharacterClass { class, allow_empty } => {
+                (*allow_empty || !section.is_empty())
+                    && section.chars().all(|c| class_contains(class, c))
+            }
+        }
+    }
+}
+
+/// Checks if a character is a member of a class like `a-z0-9_`.
+///
+/// A leading `^` negates the class, and a `-` at either end of the class is treated as a literal.
+fn class_contains(class: &str, c: char) -> bool {
+    let negated = class.len() > 1 && class.starts_with('^');
+    let mut members = if negated { class[1..].chars() } else { class.chars() };
+    let mut found = false;
+    while let Some(start) = members.next() {
+        let mut range = members.clone();
+        if let (Some('-'), Some(end)) = (range.next(), range.next()) {
+            found |= start <= c && c <= end;
+            members = range;
+        } else {
+            found |= start == c;
+        }
+    }
+    found != negated
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn class_ranges_and_literals() {
+        assert!(class_contains("a-z-", 'q'));
+        assert!(class_contains("a-z-", '-'));
+        assert!(!class_contains("a-z-", 'Q'));
+        assert!(class_contains("-0-9", '-'));
+        assert!(class_contains("^", '^'));
+    }
+
+    #[test]
+    fn negated_class() {
+        assert!(class_contains("^/", 'a'));
+        assert!(!class_contains("^/", '/'));
+        assert!(!class_contains("^a-c", 'b'));
+    }
 }
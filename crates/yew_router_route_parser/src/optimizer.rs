@@ -1,9 +1,25 @@
 use crate::{
     error::PrettyParseError,
-    parser::{parse, CaptureOrExact, RefCaptureVariant, RouteParserToken},
+    parser::{parse, CaptureOrExact, RefCaptureConstraint, RefCaptureVariant, RouteParserToken},
 };
 
-use crate::{core::FieldNamingScheme, CaptureVariant, MatcherToken};
+use crate::{
+    core::FieldNamingScheme, CaptureConstraint, CaptureVariant, MatcherToken, QueryParam, QueryValue,
+};
+
+impl<'a> From<RefCaptureConstraint<'a>> for CaptureConstraint {
+    fn from(c: RefCaptureConstraint<'a>) -> Self {
+        match c {
+            RefCaptureConstraint::Primitive(primitive) => CaptureConstraint::Primitive(primitive),
+            RefCaptureConstraint::CharacterClass { class, allow_empty } => {
+                CaptureConstraint::CharacterClass {
+                    class: class.to_string(),
+                    allow_empty,
+                }
+            }
+        }
+    }
+}
 
 impl<'a> From<RefCaptureVariant<'a>> for CaptureVariant {
     fn from(v: RefCaptureVariant<'a>) -> Self {
@@ -14,6 +30,12 @@ impl<'a> From<RefCaptureVariant<'a>> for CaptureVariant {
                 sections,
                 name: name.to_string(),
             },
+            RefCaptureVariant::ConstrainedNamed { name, constraint } => {
+                CaptureVariant::ConstrainedNamed {
+                    name: name.to_string(),
+                    constraint: constraint.into(),
+                }
+            }
             RefCaptureVariant::Unnamed => CaptureVariant::Unnamed,
             RefCaptureVariant::ManyUnnamed => CaptureVariant::ManyUnnamed,
             RefCaptureVariant::NumberedUnnamed { sections } => {
@@ -23,11 +45,13 @@ impl<'a> From<RefCaptureVariant<'a>> for CaptureVariant {
     }
 }
 
-impl<'a> From<CaptureOrExact<'a>> for MatcherToken {
+impl<'a> From<CaptureOrExact<'a>> for QueryValue {
     fn from(value: CaptureOrExact<'a>) -> Self {
         match value {
-            CaptureOrExact::Exact(m) => MatcherToken::Exact(m.to_string()),
-            CaptureOrExact::Capture(v) => MatcherToken::Capture(v.into()),
+            CaptureOrExact::Exact(m) => QueryValue::Exact(m.to_string()),
+            CaptureOrExact::Capture(v) | CaptureOrExact::OptionalCapture(v) => {
+                QueryValue::Capture(v.into())
+            }
         }
     }
 }
@@ -37,12 +61,15 @@ impl<'a> RouteParserToken<'a> {
         match self {
             RouteParserToken::Separator => "/",
             RouteParserToken::Exact(literal) => &literal,
-            RouteParserToken::QueryBegin => "?",
-            RouteParserToken::QuerySeparator => "&",
             RouteParserToken::FragmentBegin => "#",
             RouteParserToken::Nothing
+            | RouteParserToken::QueryBegin
+            | RouteParserToken::QuerySeparator
             | RouteParserToken::Capture { .. }
             | RouteParserToken::Query { .. }
+            | RouteParserToken::OneOf(_)
+            | RouteParserToken::OptionalBegin
+            | RouteParserToken::OptionalEnd
             | RouteParserToken::End => unreachable!(),
         }
     }
@@ -61,9 +88,11 @@ pub fn parse_str_and_optimize_tokens(
 ///
 /// In the process of converting the tokens, this function will condense multiple RouteParserTokens
 /// that represent literals into one Exact variant if multiple reducible tokens happen to occur in a row.
+/// The parameters of a query section are collected into a single Query variant.
 pub fn convert_tokens(tokens: &[RouteParserToken]) -> Vec<MatcherToken> {
     let mut new_tokens: Vec<MatcherToken> = vec![];
     let mut run: Vec<RouteParserToken> = vec![];
+    let mut query: Option<(bool, Vec<QueryParam>)> = None;
 
     fn empty_run(run: &mut Vec<RouteParserToken>) -> Option<MatcherToken> {
         let segment = run.iter().map(RouteParserToken::as_str).collect::<String>();
@@ -76,69 +105,100 @@ pub fn convert_tokens(tokens: &[RouteParserToken]) -> Vec<MatcherToken> {
         }
     }
 
-    fn empty_run_with_query_cap_at_end(
-        run: &mut Vec<RouteParserToken>,
-        query_lhs: &str,
-    ) -> MatcherToken {
-        let segment = run
-            .iter()
-            .map(RouteParserToken::as_str)
-            .chain(Some(query_lhs))
-            .chain(Some("="))
-            .collect::<String>();
-        run.clear();
-
-        MatcherToken::Exact(segment)
+    fn empty_query(query: &mut Option<(bool, Vec<QueryParam>)>) -> Option<MatcherToken> {
+        query
+            .take()
+            .map(|(continued, params)| MatcherToken::Query { continued, params })
     }
 
-    for token in tokens.iter() {
+    let mut index = 0;
+    while let Some(token) = tokens.get(index) {
+        index += 1;
         match token {
-            RouteParserToken::QueryBegin
-            | RouteParserToken::FragmentBegin
+            RouteParserToken::QueryBegin | RouteParserToken::QuerySeparator => {
+                if query.is_none() {
+                    if let Some(current_run) = empty_run(&mut run) {
+                        new_tokens.push(current_run);
+                    }
+                    let continued = *token == RouteParserToken::QuerySeparator;
+                    query = Some((continued, vec![]));
+                }
+            }
+            RouteParserToken::Query {
+                ident,
+                capture_or_exact,
+            } => {
+                let (_, params) = query
+                    .as_mut()
+                    .expect("Query parameters should follow a '?' or '&'");
+                let optional = matches!(capture_or_exact, CaptureOrExact::OptionalCapture(_));
+                params.push(QueryParam {
+                    key: ident.to_string(),
+                    value: QueryValue::from(*capture_or_exact),
+                    optional,
+                })
+            }
+            RouteParserToken::FragmentBegin
             | RouteParserToken::Separator
-            | RouteParserToken::QuerySeparator
-            | RouteParserToken::Exact(_) => run.push(*token),
+            | RouteParserToken::Exact(_) => {
+                if let Some(current_query) = empty_query(&mut query) {
+                    new_tokens.push(current_query);
+                }
+                run.push(*token)
+            }
             RouteParserToken::Capture(cap) => {
                 if let Some(current_run) = empty_run(&mut run) {
                     new_tokens.push(current_run);
                 }
                 new_tokens.push(MatcherToken::Capture(CaptureVariant::from(*cap)))
             }
-            RouteParserToken::Query {
-                ident,
-                capture_or_exact,
-            } => match capture_or_exact {
-                CaptureOrExact::Exact(s) => {
-                    run.push(RouteParserToken::Exact(ident));
-                    run.push(RouteParserToken::Exact("="));
-                    run.push(RouteParserToken::Exact(s));
+            RouteParserToken::OneOf(alternatives) => {
+                if let Some(current_run) = empty_run(&mut run) {
+                    new_tokens.push(current_run);
                 }
-                CaptureOrExact::Capture(cap) => {
-                    new_tokens.push(empty_run_with_query_cap_at_end(&mut run, *ident));
-                    new_tokens.push(MatcherToken::Capture(CaptureVariant::from(*cap)))
+                new_tokens.push(MatcherToken::OneOf(
+                    alternatives.split('|').map(String::from).collect(),
+                ))
+            }
+            RouteParserToken::OptionalBegin => {
+                if let Some(current_run) = empty_run(&mut run) {
+                    new_tokens.push(current_run);
                 }
-            },
+                // The parser guarantees that groups are closed and not nested.
+                let len = tokens[index..]
+                    .iter()
+                    .position(|token| *token == RouteParserToken::OptionalEnd)
+                    .expect("Optional group should be closed");
+                new_tokens.push(MatcherToken::Optional(convert_tokens(
+                    &tokens[index..index + len],
+                )));
+                index += len + 1;
+            }
+            RouteParserToken::OptionalEnd => unreachable!(),
             RouteParserToken::End => {
                 if let Some(current_run) = empty_run(&mut run) {
                     new_tokens.push(current_run);
                 }
+                if let Some(current_query) = empty_query(&mut query) {
+                    new_tokens.push(current_query);
+                }
                 new_tokens.push(MatcherToken::End);
             }
             RouteParserToken::Nothing => {}
         }
     }
 
-    // Empty the run at the end.
-    if !run.is_empty() {
-        if let Some(current_run) = empty_run(&mut run) {
-            new_tokens.push(current_run);
-        }
+    // Empty the run and query at the end.
+    if let Some(current_run) = empty_run(&mut run) {
+        new_tokens.push(current_run);
+    }
+    if let Some(current_query) = empty_query(&mut query) {
+        new_tokens.push(current_query);
     }
 
     new_tokens
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,4 +208,86 @@ mod tests {
         let tokens = parse_str_and_optimize_tokens("", FieldNamingScheme::Unit).unwrap();
         assert_eq!(tokens, vec![])
     }
+
+    #[test]
+    fn optional_group_is_condensed() {
+        let tokens =
+            parse_str_and_optimize_tokens("/posts[/page/{page}]/", FieldNamingScheme::Named)
+                .unwrap();
+        let expected = vec![
+            MatcherToken::Exact("/posts".to_string()),
+            MatcherToken::Optional(vec![
+                MatcherToken::Exact("/page/".to_string()),
+                MatcherToken::Capture(CaptureVariant::Named("page".to_string())),
+            ]),
+            MatcherToken::Exact("/".to_string()),
+        ];
+        assert_eq!(tokens, expected)
+    }
+
+    #[test]
+    fn query_is_collected() {
+        let tokens = parse_str_and_optimize_tokens(
+            "/a?lorem={ipsum}&dolor=sit&page={page?}#frag",
+            FieldNamingScheme::Named,
+        )
+        .unwrap();
+        let expected = vec![
+            MatcherToken::Exact("/a".to_string()),
+            MatcherToken::Query {
+                continued: false,
+                params: vec![
+                    QueryParam {
+                        key: "lorem".to_string(),
+                        value: QueryValue::Capture(CaptureVariant::Named("ipsum".to_string())),
+                        optional: false,
+                    },
+                    QueryParam {
+                        key: "dolor".to_string(),
+                        value: QueryValue::Exact("sit".to_string()),
+                        optional: false,
+                    },
+                    QueryParam {
+                        key: "page".to_string(),
+                        value: QueryValue::Capture(CaptureVariant::Named("page".to_string())),
+                        optional: true,
+                    },
+                ],
+            },
+            MatcherToken::Exact("#frag".to_string()),
+        ];
+        assert_eq!(tokens, expected)
+    }
+
+    #[test]
+    fn repeated_query_is_collected() {
+        let tokens =
+            parse_str_and_optimize_tokens("/a?tag={*:tags}", FieldNamingScheme::Named).unwrap();
+        let expected = vec![
+            MatcherToken::Exact("/a".to_string()),
+            MatcherToken::Query {
+                continued: false,
+                params: vec![QueryParam {
+                    key: "tag".to_string(),
+                    value: QueryValue::Capture(CaptureVariant::ManyNamed("tags".to_string())),
+                    optional: false,
+                }],
+            },
+        ];
+        assert_eq!(tokens, expected)
+    }
+
+    #[test]
+    fn one_of_is_split() {
+        let tokens =
+            parse_str_and_optimize_tokens("/(users|members)/{id}", FieldNamingScheme::Named)
+                .unwrap();
+        let expected = vec![
+            MatcherToken::Exact("/".to_string()),
+            MatcherToken::OneOf(vec!["users".to_string(), "members".to_string()]),
+            MatcherToken::Exact("/".to_string()),
+            MatcherToken::Capture(CaptureVariant::Named("id".to_string())),
+        ];
+        assert_eq!(tokens, expected)
+    }
 }
@@ -1,11 +1,11 @@
 //! Parser that consumes a string and produces the first representation of the matcher.
 use crate::{
     core::{
-        capture, exact, fragment_exact, get_and, get_end, get_hash, get_question, get_slash,
-        nothing, query,
+        capture, exact, fragment_exact, get_and, get_close_optional, get_end, get_hash,
+        get_open_optional, get_question, get_slash, nothing, one_of, query,
     },
-    error::{get_reason, ParseError, ParserErrorReason, PrettyParseError},
-    FieldNamingScheme,
+    error::{get_reason, ExpectedToken, ParseError, ParserErrorReason, PrettyParseError},
+    FieldNamingScheme, PrimitiveType,
 };
 use nom::{branch::alt, IResult};
 // use crate::core::escaped_item;
@@ -37,6 +37,14 @@ pub enum RouteParserToken<'a> {
     FragmentBegin,
     /// Match !
     End,
+    /// Match one of several literals, like (users|members).
+    ///
+    /// The alternatives are kept as they were written, separated by `|`.
+    OneOf(&'a str),
+    /// Match [
+    OptionalBegin,
+    /// Match ]
+    OptionalEnd,
 }
 
 /// Token representing various types of captures.
@@ -68,6 +76,28 @@ pub enum RefCaptureVariant<'a> {
         /// The key to be entered in the `Matches` map.
         name: &'a str,
     },
+    /// {name:constraint} - captures a section if it satisfies the constraint and adds it to the
+    /// map with a given name.
+    ConstrainedNamed {
+        /// The key to be entered in the `Matches` map.
+        name: &'a str,
+        /// The constraint the captured section must satisfy.
+        constraint: RefCaptureConstraint<'a>,
+    },
+}
+
+/// Borrowed counterpart to `CaptureConstraint`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RefCaptureConstraint<'a> {
+    /// {name:u32}
+    Primitive(PrimitiveType),
+    /// {name:[a-z]+} or {name:[a-z]*}
+    CharacterClass {
+        /// The contents of the class, found between the `[` and `]`.
+        class: &'a str,
+        /// Set if the class was followed by a `*` instead of a `+`.
+        allow_empty: bool,
+    },
 }
 
 /// Either a Capture, or an Exact match
@@ -77,6 +107,8 @@ pub enum CaptureOrExact<'a> {
     Exact(&'a str),
     /// Match a capture variant.
     Capture(RefCaptureVariant<'a>),
+    /// Match a capture variant, or nothing at all if the query parameter is absent.
+    OptionalCapture(RefCaptureVariant<'a>),
 }
 
 /// Represents the states the parser can be in.
@@ -101,17 +133,24 @@ impl<'a> ParserState<'a> {
             ParserState::None => match token {
                 RouteParserToken::Separator
                 | RouteParserToken::Exact(_)
-                | RouteParserToken::Capture(_) => Ok(ParserState::Path { prev_token: token }),
+                | RouteParserToken::OneOf(_)
+                | RouteParserToken::Capture(_)
+                | RouteParserToken::OptionalBegin => Ok(ParserState::Path { prev_token: token }),
                 RouteParserToken::QueryBegin => Ok(ParserState::FirstQuery { prev_token: token }),
                 RouteParserToken::QuerySeparator => Ok(ParserState::NthQuery { prev_token: token }),
                 RouteParserToken::Query { .. } => Err(ParserErrorReason::NotAllowedStateTransition),
                 RouteParserToken::FragmentBegin => Ok(ParserState::Fragment { prev_token: token }),
                 RouteParserToken::Nothing | RouteParserToken::End => Ok(ParserState::End),
+                RouteParserToken::OptionalEnd => Err(ParserErrorReason::NotAllowedStateTransition),
             },
             ParserState::Path { prev_token } => {
                 match prev_token {
                     RouteParserToken::Separator => match token {
-                        RouteParserToken::Exact(_) | RouteParserToken::Capture(_) => {
+                        RouteParserToken::Exact(_)
+                        | RouteParserToken::OneOf(_)
+                        | RouteParserToken::Capture(_)
+                        | RouteParserToken::OptionalBegin
+                        | RouteParserToken::OptionalEnd => {
                             Ok(ParserState::Path { prev_token: token })
                         }
                         RouteParserToken::QueryBegin => {
@@ -123,10 +162,13 @@ impl<'a> ParserState<'a> {
                         RouteParserToken::End => Ok(ParserState::End),
                         _ => Err(ParserErrorReason::NotAllowedStateTransition),
                     },
-                    RouteParserToken::Exact(_) => match token {
+                    RouteParserToken::Exact(_) | RouteParserToken::OneOf(_) => match token {
                         RouteParserToken::Exact(_)
+                        | RouteParserToken::OneOf(_)
                         | RouteParserToken::Separator
-                        | RouteParserToken::Capture(_) => {
+                        | RouteParserToken::Capture(_)
+                        | RouteParserToken::OptionalBegin
+                        | RouteParserToken::OptionalEnd => {
                             Ok(ParserState::Path { prev_token: token })
                         }
                         RouteParserToken::QueryBegin => {
@@ -139,7 +181,35 @@ impl<'a> ParserState<'a> {
                         _ => Err(ParserErrorReason::NotAllowedStateTransition),
                     },
                     RouteParserToken::Capture(_) => match token {
-                        RouteParserToken::Separator | RouteParserToken::Exact(_) => {
+                        RouteParserToken::Separator
+                        | RouteParserToken::Exact(_)
+                        | RouteParserToken::OneOf(_)
+                        | RouteParserToken::OptionalBegin
+                        | RouteParserToken::OptionalEnd => {
+                            Ok(ParserState::Path { prev_token: token })
+                        }
+                        RouteParserToken::QueryBegin => {
+                            Ok(ParserState::FirstQuery { prev_token: token })
+                        }
+                        RouteParserToken::FragmentBegin => {
+                            Ok(ParserState::Fragment { prev_token: token })
+                        }
+                        RouteParserToken::End => Ok(ParserState::End),
+                        _ => Err(ParserErrorReason::NotAllowedStateTransition),
+                    },
+                    RouteParserToken::OptionalBegin => match token {
+                        RouteParserToken::Separator
+                        | RouteParserToken::Exact(_)
+                        | RouteParserToken::OneOf(_) => {
+                            Ok(ParserState::Path { prev_token: token })
+                        }
+                        _ => Err(ParserErrorReason::NotAllowedStateTransition),
+                    },
+                    RouteParserToken::OptionalEnd => match token {
+                        RouteParserToken::Separator
+                        | RouteParserToken::Exact(_)
+                        | RouteParserToken::OneOf(_)
+                        | RouteParserToken::OptionalBegin => {
                             Ok(ParserState::Path { prev_token: token })
                         }
                         RouteParserToken::QueryBegin => {
@@ -220,6 +290,7 @@ pub fn parse(
     let input = i;
     let mut tokens: Vec<RouteParserToken> = vec![];
     let mut state = ParserState::None;
+    let mut in_optional = false;
 
     loop {
         let (ii, token) = parse_impl(i, &state, field_naming_scheme).map_err(|e| match e {
@@ -230,7 +301,20 @@ pub fn parse(
             },
             _ => panic!("parser should not be incomplete"),
         })?;
+        let remaining = i;
         i = ii;
+        in_optional = check_optional_nesting(in_optional, token).map_err(|reason| {
+            let error = ParseError {
+                reason: Some(reason),
+                expected: vec![],
+                offset: 0,
+            };
+            PrettyParseError {
+                error,
+                input,
+                remaining,
+            }
+        })?;
         state = state.transition(token.clone()).map_err(|reason| {
             let error = ParseError {
                 reason: Some(reason),
@@ -250,9 +334,48 @@ pub fn parse(
             break;
         }
     }
+
+    if in_optional {
+        let error = ParseError {
+            reason: Some(ParserErrorReason::UnclosedOptional),
+            expected: vec![ExpectedToken::CloseOptional],
+            offset: 0,
+        };
+        return Err(PrettyParseError {
+            error,
+            input,
+            remaining: i,
+        });
+    }
     Ok(tokens)
 }
 
+/// Tracks if the parser is within an optional group, given the last parsed token.
+///
+/// Optional groups can't be nested, and must be closed before the path section ends.
+fn check_optional_nesting(
+    in_optional: bool,
+    token: RouteParserToken,
+) -> Result<bool, ParserErrorReason> {
+    match token {
+        RouteParserToken::OptionalBegin if in_optional => Err(ParserErrorReason::NestedOptional),
+        RouteParserToken::OptionalBegin => Ok(true),
+        RouteParserToken::OptionalEnd if !in_optional => {
+            Err(ParserErrorReason::UnopenedOptional)
+        }
+        RouteParserToken::OptionalEnd => Ok(false),
+        RouteParserToken::QueryBegin
+        | RouteParserToken::QuerySeparator
+        | RouteParserToken::FragmentBegin
+        | RouteParserToken::End
+            if in_optional =>
+        {
+            Err(ParserErrorReason::UnclosedOptional)
+        }
+        _ => Ok(in_optional),
+    }
+}
+
 fn parse_impl<'a>(
     i: &'a str,
     state: &ParserState,
@@ -266,6 +389,8 @@ fn parse_impl<'a>(
             get_hash,
             capture(field_naming_scheme),
             exact,
+            one_of,
+            get_open_optional,
             get_end,
             nothing,
         ))(i),
@@ -273,7 +398,10 @@ fn parse_impl<'a>(
             RouteParserToken::Separator => {
                 alt((
                     exact,
+                    one_of,
                     capture(field_naming_scheme),
+                    get_open_optional,
+                    get_close_optional,
                     get_question,
                     get_hash,
                     get_end,
@@ -289,11 +417,14 @@ fn parse_impl<'a>(
                     e
                 })
             }
-            RouteParserToken::Exact(_) => {
+            RouteParserToken::Exact(_) | RouteParserToken::OneOf(_) => {
                 alt((
                     get_slash,
                     exact, // This will handle escaped items
+                    one_of,
                     capture(field_naming_scheme),
+                    get_open_optional,
+                    get_close_optional,
                     get_question,
                     get_hash,
                     get_end,
@@ -309,8 +440,17 @@ fn parse_impl<'a>(
                 })
             }
             RouteParserToken::Capture(_) => {
-                alt((get_slash, exact, get_question, get_hash, get_end))(i).map_err(
-                    |mut e: nom::Err<ParseError>| {
+                alt((
+                    get_slash,
+                    exact,
+                    one_of,
+                    get_open_optional,
+                    get_close_optional,
+                    get_question,
+                    get_hash,
+                    get_end,
+                ))(i)
+                .map_err(|mut e: nom::Err<ParseError>| {
                         // Detect likely failures if the above failed to match.
                         let reason: &mut Option<ParserErrorReason> = get_reason(&mut e);
                         *reason = capture(field_naming_scheme)(i)
@@ -319,9 +459,18 @@ fn parse_impl<'a>(
                             .ok()
                             .or(*reason);
                         e
-                    },
-                )
+                })
             }
+            RouteParserToken::OptionalBegin => alt((get_slash, exact, one_of))(i),
+            RouteParserToken::OptionalEnd => alt((
+                get_slash,
+                exact,
+                one_of,
+                get_open_optional,
+                get_question,
+                get_hash,
+                get_end,
+            ))(i),
             _ => Err(nom::Err::Failure(ParseError {
                 reason: Some(ParserErrorReason::InvalidState),
                 expected: vec![],
@@ -390,7 +539,10 @@ fn parse_impl<'a>(
             RouteParserToken::FragmentBegin => {
                 alt((fragment_exact, capture(field_naming_scheme), get_end))(i)
             }
-            RouteParserToken::Exact(_) => alt((capture(field_naming_scheme), get_end))(i),
+            RouteParserToken::Exact(_) => {
+                // Another exact is allowed, so escaped items can follow a literal.
+                alt((fragment_exact, capture(field_naming_scheme), get_end))(i)
+            }
             RouteParserToken::Capture(_) => alt((fragment_exact, get_end))(i),
             _ => Err(nom::Err::Failure(ParseError {
                 reason: Some(ParserErrorReason::InvalidState),
@@ -542,6 +694,18 @@ mod test {
             ];
             assert_eq!(tokens, expected);
         }
+
+        #[test]
+        fn escaped_bracket_in_fragment() {
+            let tokens = parse(r#"#escaped{{bracket"#).expect("should parse");
+            let expected = vec![
+                RouteParserToken::FragmentBegin,
+                RouteParserToken::Exact(r#"escaped"#),
+                RouteParserToken::Exact(r#"{"#),
+                RouteParserToken::Exact(r#"bracket"#),
+            ];
+            assert_eq!(tokens, expected);
+        }
     }
 
     mod does_not_parse {
@@ -572,6 +736,50 @@ mod test {
         }
 
 
+        #[test]
+        fn unknown_constraint() {
+            let x = parse("/{id:integer}").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::BadConstraint));
+            assert_eq!(x.error.offset, 4);
+        }
+
+        #[test]
+        fn class_without_quantifier() {
+            let x = parse("/{s:[a-z]}").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::BadConstraint));
+        }
+
+        #[test]
+        fn nested_optional() {
+            let x = parse("/a[/b[/c]]").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::NestedOptional));
+        }
+
+        #[test]
+        fn unclosed_optional() {
+            let x = parse("/a[/b").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::UnclosedOptional));
+            let x = parse("/a[/b?query=this").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::UnclosedOptional));
+        }
+
+        #[test]
+        fn unopened_optional() {
+            let x = parse("/a/b]").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::UnopenedOptional));
+        }
+
+        #[test]
+        fn empty_alternative() {
+            let x = parse("/(users|)").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::EmptyAlternative));
+        }
+
+        #[test]
+        fn empty_optional() {
+            parse("/a[]").expect_err("Should not parse");
+        }
+
         #[test]
         fn after_end() {
             let x = parse("/lorem/ipsum!/dolor").expect_err("Should not parse");
@@ -581,7 +789,10 @@ mod test {
 
     mod correct_parse {
         use super::*;
-        use crate::parser::{CaptureOrExact, RefCaptureVariant};
+        use crate::{
+            parser::{CaptureOrExact, RefCaptureConstraint, RefCaptureVariant},
+            PrimitiveType,
+        };
 
         #[test]
         fn starting_literal() {
@@ -628,6 +839,65 @@ mod test {
             assert_eq!(parsed, expected);
         }
 
+        #[test]
+        fn constrained_capture_path() {
+            let parsed = parse("/{id:u32}/{slug:[a-z-]*}").unwrap();
+            let expected = vec![
+                RouteParserToken::Separator,
+                RouteParserToken::Capture(RefCaptureVariant::ConstrainedNamed {
+                    name: "id",
+                    constraint: RefCaptureConstraint::Primitive(PrimitiveType::U32),
+                }),
+                RouteParserToken::Separator,
+                RouteParserToken::Capture(RefCaptureVariant::ConstrainedNamed {
+                    name: "slug",
+                    constraint: RefCaptureConstraint::CharacterClass {
+                        class: "a-z-",
+                        allow_empty: true,
+                    },
+                }),
+            ];
+            assert_eq!(parsed, expected);
+        }
+
+        #[test]
+        fn optional_group() {
+            let parsed = parse("/posts[/{page}]").unwrap();
+            let expected = vec![
+                RouteParserToken::Separator,
+                RouteParserToken::Exact("posts"),
+                RouteParserToken::OptionalBegin,
+                RouteParserToken::Separator,
+                RouteParserToken::Capture(RefCaptureVariant::Named("page")),
+                RouteParserToken::OptionalEnd,
+            ];
+            assert_eq!(parsed, expected);
+        }
+
+        #[test]
+        fn escaped_brackets() {
+            let parsed = parse("/[[a]]").unwrap();
+            let expected = vec![
+                RouteParserToken::Separator,
+                RouteParserToken::Exact("["),
+                RouteParserToken::Exact("a"),
+                RouteParserToken::Exact("]"),
+            ];
+            assert_eq!(parsed, expected);
+        }
+
+        #[test]
+        fn one_of() {
+            let parsed = parse("/(users|members)/{id}").unwrap();
+            let expected = vec![
+                RouteParserToken::Separator,
+                RouteParserToken::OneOf("users|members"),
+                RouteParserToken::Separator,
+                RouteParserToken::Capture(RefCaptureVariant::Named("id")),
+            ];
+            assert_eq!(parsed, expected);
+        }
+
         #[test]
         fn query() {
             let parsed = parse("?query=this").unwrap();
@@ -1,5 +1,10 @@
 //! Bridge to RouteAgent.
-use crate::{agent::RouteAgent, route::Route, RouteState};
+use crate::{
+    agent::RouteAgent,
+    history::{BrowserHistory, HistoryBackend},
+    route::Route,
+    RouteState,
+};
 use std::{
     fmt::{Debug, Error as FmtError, Formatter},
     ops::{Deref, DerefMut},
@@ -12,13 +17,17 @@ use yew::{
 /// A wrapped bridge to the route agent.
 ///
 /// A component that owns this can send and receive messages from the agent.
-pub struct RouteAgentBridge<STATE = ()>(Box<dyn Bridge<RouteAgent<STATE>>>)
+pub struct RouteAgentBridge<STATE = (), HISTORY = BrowserHistory<STATE>>(
+    Box<dyn Bridge<RouteAgent<STATE, HISTORY>>>,
+)
 where
-    STATE: RouteState;
+    STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static;
 
-impl<STATE> RouteAgentBridge<STATE>
+impl<STATE, HISTORY> RouteAgentBridge<STATE, HISTORY>
 where
     STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
 {
     /// Creates a new bridge.
     pub fn new(callback: Callback<Route<STATE>>) -> Self {
@@ -36,20 +45,32 @@ where
     }
 }
 
-impl<STATE: RouteState> Debug for RouteAgentBridge<STATE> {
+impl<STATE, HISTORY> Debug for RouteAgentBridge<STATE, HISTORY>
+where
+    STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
+{
     fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
         f.debug_tuple("RouteAgentBridge").finish()
     }
 }
 
-impl<STATE: RouteState> Deref for RouteAgentBridge<STATE> {
-    type Target = Box<dyn Bridge<RouteAgent<STATE>>>;
+impl<STATE, HISTORY> Deref for RouteAgentBridge<STATE, HISTORY>
+where
+    STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
+{
+    type Target = Box<dyn Bridge<RouteAgent<STATE, HISTORY>>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
-impl<STATE: RouteState> DerefMut for RouteAgentBridge<STATE> {
+impl<STATE, HISTORY> DerefMut for RouteAgentBridge<STATE, HISTORY>
+where
+    STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
+{
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
@@ -1,5 +1,9 @@
 //! Dispatcher to RouteAgent.
-use crate::{agent::RouteAgent, RouteState};
+use crate::{
+    agent::RouteAgent,
+    history::{BrowserHistory, HistoryBackend},
+    RouteState,
+};
 use std::{
     fmt::{Debug, Error as FmtError, Formatter},
     ops::{Deref, DerefMut},
@@ -9,13 +13,17 @@ use yew::agent::{Dispatched, Dispatcher};
 /// A wrapped dispatcher to the route agent.
 ///
 /// A component that owns and instance of this can send messages to the RouteAgent, but not receive them.
-pub struct RouteAgentDispatcher<STATE = ()>(Dispatcher<RouteAgent<STATE>>)
+pub struct RouteAgentDispatcher<STATE = (), HISTORY = BrowserHistory<STATE>>(
+    Dispatcher<RouteAgent<STATE, HISTORY>>,
+)
 where
-    STATE: RouteState;
+    STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static;
 
-impl<STATE> RouteAgentDispatcher<STATE>
+impl<STATE, HISTORY> RouteAgentDispatcher<STATE, HISTORY>
 where
     STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
 {
     /// Creates a new bridge.
     pub fn new() -> Self {
@@ -24,29 +32,42 @@ where
     }
 }
 
-impl<STATE> Default for RouteAgentDispatcher<STATE>
+impl<STATE, HISTORY> Default for RouteAgentDispatcher<STATE, HISTORY>
 where
     STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
 {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<STATE: RouteState> Debug for RouteAgentDispatcher<STATE> {
+impl<STATE, HISTORY> Debug for RouteAgentDispatcher<STATE, HISTORY>
+where
+    STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
+{
     fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
         f.debug_tuple("RouteAgentDispatcher").finish()
     }
 }
 
-impl<STATE: RouteState> Deref for RouteAgentDispatcher<STATE> {
-    type Target = Dispatcher<RouteAgent<STATE>>;
+impl<STATE, HISTORY> Deref for RouteAgentDispatcher<STATE, HISTORY>
+where
+    STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
+{
+    type Target = Dispatcher<RouteAgent<STATE, HISTORY>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
-impl<T: RouteState> DerefMut for RouteAgentDispatcher<T> {
+impl<STATE, HISTORY> DerefMut for RouteAgentDispatcher<STATE, HISTORY>
+where
+    STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
+{
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
@@ -2,7 +2,11 @@
 //!
 //! It wraps a route service and allows calls to be sent to it to update every subscriber,
 //! or just the element that made the request.
-use crate::service::RouteService;
+use crate::{
+    guard::{GuardDecision, Guards, Navigation, NavigationGuard, NavigationKind},
+    history::{BrowserHistory, HistoryBackend},
+    service::RouteService,
+};
 
 use yew::prelude::worker::*;
 
@@ -44,6 +48,20 @@ pub enum RouteRequest<T = ()> {
     ChangeRouteNoBroadcast(Route<T>),
     /// Gets the current route.
     GetCurrentRoute,
+    /// Sets a guard that can block or redirect the navigations of `ChangeRoute` and
+    /// `ReplaceRoute` requests, and of the browser's back and forward buttons,
+    /// replacing any guard the sender set before.
+    ///
+    /// Requests that don't alert connected components aren't guarded.
+    /// The guard is removed when the sender disconnects.
+    #[serde(skip)]
+    SetGuard(NavigationGuard<T>),
+    /// Removes the guard set by the sender.
+    RemoveGuard,
+    /// Sets whether the sender wants users to confirm leaving the application,
+    /// like by closing the tab.
+    /// Users are asked while any connected entity wants them to be.
+    PromptBeforeUnload(bool),
 }
 
 /// The RouteAgent holds on to the RouteService singleton and mediates access to it.
@@ -56,49 +74,86 @@ pub enum RouteRequest<T = ()> {
 ///
 /// If you use multiple agents with different types, then the Agents won't be able to communicate to
 /// each other and associated components may not work as intended.
-pub struct RouteAgent<STATE = ()>
+///
+/// The agent navigates through the browser's history by default.
+/// Another `HistoryBackend`, like `MemoryHistory`, can be used instead, and is created with its
+/// `Default` implementation when the agent is.
+///
+/// Connected entities can block or redirect navigation by setting a guard with
+/// `RouteRequest::SetGuard`, and ask users to confirm leaving the application with
+/// `RouteRequest::PromptBeforeUnload`.
+pub struct RouteAgent<STATE = (), HISTORY = BrowserHistory<STATE>>
 where
     STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
 {
     // In order to have the AgentLink<Self> below, apparently T must be constrained like this.
     // Unfortunately, this means that everything related to an agent requires this constraint.
-    link: AgentLink<RouteAgent<STATE>>,
-    /// The service through which communication with the browser happens.
-    route_service: RouteService<STATE>,
+    link: AgentLink<RouteAgent<STATE, HISTORY>>,
+    /// The service through which communication with the history happens.
+    route_service: RouteService<STATE, HISTORY>,
     /// A list of all entities connected to the router.
     /// When a route changes, either initiated by the browser or by the app,
     /// the route change will be broadcast to all listening entities.
     subscribers: HashSet<HandlerId>,
+    /// The route that was navigated to last, which guards see navigations from.
+    route: Route<STATE>,
+    /// The position of the route in the history, if the history knows it.
+    position: Option<usize>,
+    /// The position the history is going back to after a blocked navigation,
+    /// whose change isn't a navigation of its own.
+    restoring: Option<usize>,
+    /// The guards set by connected entities.
+    guards: Guards<HandlerId, STATE>,
+    /// The entities that want users to confirm leaving the application.
+    unload_prompters: HashSet<HandlerId>,
 }
 
-impl<STATE: RouteState> Debug for RouteAgent<STATE> {
+impl<STATE, HISTORY> Debug for RouteAgent<STATE, HISTORY>
+where
+    STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + Debug + 'static,
+{
     fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
         f.debug_struct("RouteAgent")
             .field("link", &"-")
             .field("route_service", &self.route_service)
             .field("subscribers", &self.subscribers.len())
+            .field("route", &self.route)
+            .field("position", &self.position)
+            .field("restoring", &self.restoring)
+            .field("guards", &self.guards)
+            .field("unload_prompters", &self.unload_prompters.len())
             .finish()
     }
 }
 
-impl<STATE> Agent for RouteAgent<STATE>
+impl<STATE, HISTORY> Agent for RouteAgent<STATE, HISTORY>
 where
     STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
 {
     type Input = RouteRequest<STATE>;
     type Message = Msg<STATE>;
     type Output = Route<STATE>;
     type Reach = Context;
 
-    fn create(link: AgentLink<RouteAgent<STATE>>) -> Self {
+    fn create(link: AgentLink<RouteAgent<STATE, HISTORY>>) -> Self {
         let callback = link.callback(Msg::BrowserNavigationRouteChanged);
-        let mut route_service = RouteService::new();
+        let mut route_service = RouteService::with_history(HISTORY::default());
         route_service.register_callback(callback);
+        let route = route_service.get_route();
+        let position = route_service.history().position();
 
         RouteAgent {
             link,
             route_service,
             subscribers: HashSet::new(),
+            route,
+            position,
+            restoring: None,
+            guards: Guards::new(),
+            unload_prompters: HashSet::new(),
         }
     }
 
@@ -106,9 +161,27 @@ where
         match msg {
             Msg::BrowserNavigationRouteChanged(route) => {
                 trace!("Browser navigated");
-                for sub in &self.subscribers {
-                    self.link.respond(*sub, route.clone());
+                let position = self.route_service.history().position();
+                if let Some(restoring) = self.restoring.take() {
+                    if position == Some(restoring) {
+                        trace!("Went back to the route a navigation was blocked from");
+                        return;
+                    }
                 }
+                let route = match self.check_guards(&route, NavigationKind::Pop) {
+                    GuardDecision::Allow => route,
+                    GuardDecision::Block => {
+                        trace!("Navigation was blocked by a guard");
+                        self.restore_position(position);
+                        return;
+                    }
+                    GuardDecision::Redirect(route) => {
+                        let route_string: String = route.to_string();
+                        self.route_service.replace_route(&route_string, route.state);
+                        self.route_service.get_route()
+                    }
+                };
+                self.set_current(route);
             }
         }
     }
@@ -120,40 +193,370 @@ where
     fn handle_input(&mut self, msg: Self::Input, who: HandlerId) {
         match msg {
             RouteRequest::ReplaceRoute(route) => {
+                let route = match self.check_guards(&route, NavigationKind::Replace) {
+                    GuardDecision::Allow => route,
+                    GuardDecision::Block => return,
+                    GuardDecision::Redirect(route) => route,
+                };
                 let route_string: String = route.to_string();
                 self.route_service.replace_route(&route_string, route.state);
                 let route = self.route_service.get_route();
-                for sub in &self.subscribers {
-                    self.link.respond(*sub, route.clone());
-                }
+                self.set_current(route);
             }
             RouteRequest::ReplaceRouteNoBroadcast(route) => {
                 let route_string: String = route.to_string();
                 self.route_service.replace_route(&route_string, route.state);
+                self.route = self.route_service.get_route();
+                self.position = self.route_service.history().position();
             }
             RouteRequest::ChangeRoute(route) => {
+                // let the guards block or redirect the navigation
+                let route = match self.check_guards(&route, NavigationKind::Push) {
+                    GuardDecision::Allow => route,
+                    GuardDecision::Block => return,
+                    GuardDecision::Redirect(route) => route,
+                };
                 let route_string: String = route.to_string();
                 // set the route
                 self.route_service.set_route(&route_string, route.state);
-                // get the new route.
+                // get the new route, and broadcast it to all listening components
                 let route = self.route_service.get_route();
-                // broadcast it to all listening components
-                for sub in &self.subscribers {
-                    self.link.respond(*sub, route.clone());
-                }
+                self.set_current(route);
             }
             RouteRequest::ChangeRouteNoBroadcast(route) => {
                 let route_string: String = route.to_string();
                 self.route_service.set_route(&route_string, route.state);
+                self.route = self.route_service.get_route();
+                self.position = self.route_service.history().position();
             }
             RouteRequest::GetCurrentRoute => {
                 let route = self.route_service.get_route();
                 self.link.respond(who, route);
             }
+            RouteRequest::SetGuard(guard) => {
+                self.guards.set(who, guard);
+            }
+            RouteRequest::RemoveGuard => {
+                self.guards.remove(&who);
+            }
+            RouteRequest::PromptBeforeUnload(prompt) => {
+                if prompt {
+                    self.unload_prompters.insert(who);
+                } else {
+                    self.unload_prompters.remove(&who);
+                }
+                self.update_unload_prompt();
+            }
         }
     }
 
     fn disconnected(&mut self, id: HandlerId) {
         self.subscribers.remove(&id);
+        self.guards.remove(&id);
+        if self.unload_prompters.remove(&id) {
+            self.update_unload_prompt();
+        }
+    }
+}
+
+impl<STATE, HISTORY> RouteAgent<STATE, HISTORY>
+where
+    STATE: RouteState,
+    HISTORY: HistoryBackend<STATE> + Default + 'static,
+{
+    /// Decides whether the current route may be left for the route.
+    fn check_guards(&self, to: &Route<STATE>, kind: NavigationKind) -> GuardDecision<STATE> {
+        self.guards.check(&Navigation {
+            from: &self.route,
+            to,
+            kind,
+        })
+    }
+
+    /// Makes the route the current one, and broadcasts it to every subscriber.
+    fn set_current(&mut self, route: Route<STATE>) {
+        self.route = route.clone();
+        self.position = self.route_service.history().position();
+        for sub in &self.subscribers {
+            self.link.respond(*sub, route.clone());
+        }
+    }
+
+    /// Goes back to the route that was left by a blocked navigation through the history,
+    /// which has already happened.
+    ///
+    /// If the history doesn't know where the route is, the route replaces the current entry
+    /// instead, so that the entries after it are kept.
+    fn restore_position(&mut self, position: Option<usize>) {
+        match (self.position, position) {
+            (Some(left), Some(position)) if left != position => {
+                self.restoring = Some(left);
+                self.route_service.go(left as i32 - position as i32);
+            }
+            _ => {
+                let previous = self.route.clone();
+                self.route_service.replace_route(&previous.to_string(), previous.state);
+                self.position = self.route_service.history().position();
+            }
+        }
+    }
+
+    fn update_unload_prompt(&mut self) {
+        let prompt = !self.unload_prompters.is_empty();
+        self.route_service.prompt_before_unload(prompt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{HistoryListener, MemoryHistory};
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+    use yew::agent::{AgentLifecycleEvent, AgentScope, Responder};
+
+    thread_local! {
+        /// The history of the agent under test, which tests navigate through like users would
+        /// with the back and forward buttons.
+        static HISTORY: RefCell<MemoryHistory> = RefCell::new(MemoryHistory::new("/"));
+        static LISTENER: RefCell<Option<HistoryListener<()>>> = RefCell::new(None);
+        /// Whether the history reports the position of its entries.
+        static KNOWS_POSITION: Cell<bool> = Cell::new(true);
+    }
+
+    /// A `MemoryHistory` that is shared with the test.
+    ///
+    /// The listener is kept apart from it, so that the agent can use the history while it
+    /// handles a navigation.
+    #[derive(Default)]
+    struct SharedHistory;
+
+    impl HistoryBackend<()> for SharedHistory {
+        fn push(&mut self, route: &str, state: ()) {
+            HISTORY.with(|history| history.borrow_mut().push(route, state))
+        }
+
+        fn replace(&mut self, route: &str, state: ()) {
+            HISTORY.with(|history| history.borrow_mut().replace(route, state))
+        }
+
+        fn current(&self) -> Route<()> {
+            HISTORY.with(|history| history.borrow().current())
+        }
+
+        fn listen(&mut self, listener: HistoryListener<()>) {
+            LISTENER.with(|cell| *cell.borrow_mut() = Some(listener))
+        }
+
+        fn go(&mut self, delta: i32) {
+            let moved = HISTORY.with(|history| {
+                let mut history = history.borrow_mut();
+                let index = history.index();
+                history.go(delta);
+                history.index() != index
+            });
+            if moved {
+                let route = self.current();
+                LISTENER.with(|cell| {
+                    if let Some(listener) = cell.borrow().as_ref() {
+                        listener(route)
+                    }
+                });
+            }
+        }
+
+        fn position(&self) -> Option<usize> {
+            if !KNOWS_POSITION.with(Cell::get) {
+                return None;
+            }
+            HISTORY.with(|history| history.borrow().position())
+        }
+    }
+
+    type TestAgent = RouteAgent<(), SharedHistory>;
+    type Responses = Rc<RefCell<Vec<(HandlerId, Route<()>)>>>;
+
+    struct Recorder(Responses);
+
+    impl Responder<TestAgent> for Recorder {
+        fn respond(&self, id: HandlerId, output: Route<()>) {
+            self.0.borrow_mut().push((id, output));
+        }
+    }
+
+    /// Runs an agent with connected subscribers, recording what it responds with.
+    struct Harness {
+        scope: AgentScope<TestAgent>,
+        responses: Responses,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let scope = AgentScope::new();
+            let responses = Rc::new(RefCell::new(Vec::new()));
+            let link = AgentLink::connect(&scope, Recorder(responses.clone()));
+            scope.send(AgentLifecycleEvent::Create(link));
+            scope.send(AgentLifecycleEvent::Connected(subscriber(1)));
+            scope.send(AgentLifecycleEvent::Connected(subscriber(2)));
+            Harness { scope, responses }
+        }
+
+        fn send(&self, request: RouteRequest<()>) {
+            self.scope.send(AgentLifecycleEvent::Input(request, subscriber(1)));
+        }
+
+        /// Takes the routes that were broadcast to every subscriber.
+        fn broadcasts(&self) -> Vec<String> {
+            let responses: Vec<_> = self.responses.borrow_mut().drain(..).collect();
+            let to = |id| {
+                responses
+                    .iter()
+                    .filter(|(sub, _)| *sub == id)
+                    .map(|(_, route)| route.route.clone())
+                    .collect::<Vec<_>>()
+            };
+            let broadcasts = to(subscriber(1));
+            assert_eq!(broadcasts, to(subscriber(2)));
+            broadcasts
+        }
+    }
+
+    /// Handler ids can only be made by yew, or deserialized.
+    fn subscriber(id: usize) -> HandlerId {
+        serde_json::from_value(serde_json::json!([id, true])).unwrap()
+    }
+
+    /// Gets the routes of the history, and the position of the current one.
+    fn history() -> (Vec<String>, usize) {
+        HISTORY.with(|history| {
+            let history = history.borrow();
+            let routes = history.entries().iter().map(|r| r.route.clone()).collect();
+            (routes, history.index())
+        })
+    }
+
+    #[test]
+    fn change_route_is_pushed_and_broadcast() {
+        let agent = Harness::new();
+        agent.send(RouteRequest::ChangeRoute(Route::new_no_state("/users")));
+        assert_eq!(agent.broadcasts(), vec!["/users"]);
+        assert_eq!(history(), (vec!["/".to_string(), "/users".to_string()], 1));
+
+        agent.send(RouteRequest::ChangeRouteNoBroadcast(Route::new_no_state("/about")));
+        assert!(agent.broadcasts().is_empty());
+        assert_eq!(history().1, 2);
+    }
+
+    #[test]
+    fn replace_route_is_replaced_and_broadcast() {
+        let agent = Harness::new();
+        agent.send(RouteRequest::ReplaceRoute(Route::new_no_state("/users")));
+        assert_eq!(agent.broadcasts(), vec!["/users"]);
+        assert_eq!(history(), (vec!["/users".to_string()], 0));
+
+        agent.send(RouteRequest::ReplaceRouteNoBroadcast(Route::new_no_state("/about")));
+        assert!(agent.broadcasts().is_empty());
+        assert_eq!(history(), (vec!["/about".to_string()], 0));
+    }
+
+    #[test]
+    fn back_navigation_is_broadcast() {
+        let agent = Harness::new();
+        agent.send(RouteRequest::ChangeRoute(Route::new_no_state("/users")));
+        agent.broadcasts();
+
+        SharedHistory.go(-1);
+        assert_eq!(agent.broadcasts(), vec!["/"]);
+        SharedHistory.go(1);
+        assert_eq!(agent.broadcasts(), vec!["/users"]);
+    }
+
+    /// Pushes routes to the history, and sets a guard that decides on going back and forth
+    /// through it.
+    fn guarded_agent<F>(routes: &[&str], guard: F) -> Harness
+    where
+        F: Fn(&Navigation<()>) -> GuardDecision<()> + 'static,
+    {
+        let agent = Harness::new();
+        for route in routes {
+            agent.send(RouteRequest::ChangeRoute(Route::new_no_state(*route)));
+        }
+        agent.broadcasts();
+        agent.send(RouteRequest::SetGuard(NavigationGuard::new(move |navigation| {
+            match navigation.kind {
+                NavigationKind::Pop => guard(navigation),
+                _ => GuardDecision::Allow,
+            }
+        })));
+        agent
+    }
+
+    #[test]
+    fn blocked_back_navigation_goes_back_to_the_route_that_was_left() {
+        let navigations = Rc::new(RefCell::new(Vec::new()));
+        let agent = {
+            let navigations = navigations.clone();
+            guarded_agent(&["/users", "/about"], move |navigation| {
+                let from = navigation.from.route.clone();
+                navigations.borrow_mut().push((from, navigation.to.route.clone()));
+                GuardDecision::Block
+            })
+        };
+
+        SharedHistory.go(-2);
+        assert!(agent.broadcasts().is_empty());
+        let routes = vec!["/".to_string(), "/users".to_string(), "/about".to_string()];
+        assert_eq!(history(), (routes, 2));
+        // Going back to the route that was left isn't a navigation of its own.
+        assert_eq!(*navigations.borrow(), vec![("/about".to_string(), "/".to_string())]);
+
+        SharedHistory.go(-1);
+        assert!(agent.broadcasts().is_empty());
+        assert_eq!(history().1, 2);
+        assert_eq!(navigations.borrow().len(), 2);
+    }
+
+    #[test]
+    fn blocked_back_navigation_replaces_the_route_if_the_position_is_unknown() {
+        KNOWS_POSITION.with(|knows_position| knows_position.set(false));
+        let agent = guarded_agent(&["/users", "/about"], |_| GuardDecision::Block);
+
+        SharedHistory.go(-1);
+        assert!(agent.broadcasts().is_empty());
+        // The forward history is kept.
+        let routes = vec!["/".to_string(), "/about".to_string(), "/about".to_string()];
+        assert_eq!(history(), (routes, 1));
+    }
+
+    #[test]
+    fn redirected_back_navigation_replaces_the_route() {
+        let agent = guarded_agent(&["/users", "/about"], |_| {
+            GuardDecision::Redirect(Route::new_no_state("/login"))
+        });
+
+        SharedHistory.go(-1);
+        assert_eq!(agent.broadcasts(), vec!["/login"]);
+        let routes = vec!["/".to_string(), "/login".to_string(), "/about".to_string()];
+        assert_eq!(history(), (routes, 1));
+    }
+
+    #[test]
+    fn allowed_back_navigation_is_broadcast() {
+        let agent = guarded_agent(&["/users"], |_| GuardDecision::Allow);
+
+        SharedHistory.go(-1);
+        assert_eq!(agent.broadcasts(), vec!["/"]);
+        assert_eq!(history().1, 0);
+    }
+
+    #[test]
+    fn current_route_is_sent_to_the_requester() {
+        let agent = Harness::new();
+        agent.send(RouteRequest::ChangeRouteNoBroadcast(Route::new_no_state("/users")));
+        agent.send(RouteRequest::GetCurrentRoute);
+        let responses = agent.responses.borrow();
+        assert_eq!(*responses, vec![(subscriber(1), Route::new_no_state("/users"))]);
     }
 }
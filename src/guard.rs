@@ -0,0 +1,245 @@
+//! Guards that can block or redirect navigation.
+//!
+//! Subscribers of the `RouteAgent` set a guard by sending it `RouteRequest::SetGuard`,
+//! for example to keep users from leaving a form with unsaved changes.
+//!
+//! # Example
+//! ```
+//! use yew_router::{
+//!     guard::{GuardDecision, Guards, Navigation, NavigationGuard, NavigationKind},
+//!     route::Route,
+//! };
+//! let mut guards = Guards::new();
+//! guards.set(
+//!     "wizard",
+//!     NavigationGuard::new(|navigation: &Navigation<()>| {
+//!         if navigation.from.starts_with("/wizard") && !navigation.to.starts_with("/wizard") {
+//!             GuardDecision::Block
+//!         } else {
+//!             GuardDecision::Allow
+//!         }
+//!     }),
+//! );
+//!
+//! let from = Route::new_no_state("/wizard/2");
+//! let to = Route::new_no_state("/about");
+//! let decision = guards.check(&Navigation {
+//!     from: &from,
+//!     to: &to,
+//!     kind: NavigationKind::Push,
+//! });
+//! assert_eq!(decision, GuardDecision::Block);
+//! ```
+use crate::route::Route;
+use std::{
+    fmt::{self, Debug, Formatter},
+    rc::Rc,
+};
+
+/// How a navigation was started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigationKind {
+    /// A new entry is added to the history, like by `RouteRequest::ChangeRoute`.
+    Push,
+    /// The current entry of the history is replaced, like by `RouteRequest::ReplaceRoute`.
+    Replace,
+    /// The user moved through the history, like with the back or forward buttons.
+    ///
+    /// The url has already changed when guards are checked,
+    /// so the history goes back to the route that was left if the navigation is blocked.
+    /// The route replaces the current entry instead if the `HistoryBackend` doesn't know
+    /// the position of the entries.
+    Pop,
+}
+
+/// A navigation that is about to happen.
+#[derive(Debug)]
+pub struct Navigation<'a, STATE> {
+    /// The current route.
+    pub from: &'a Route<STATE>,
+    /// The route that is navigated to.
+    pub to: &'a Route<STATE>,
+    /// How the navigation was started.
+    pub kind: NavigationKind,
+}
+
+/// What a guard decided about a navigation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GuardDecision<STATE> {
+    /// The navigation may happen.
+    Allow,
+    /// The navigation doesn't happen, and the current route is kept.
+    Block,
+    /// The navigation goes to another route instead.
+    Redirect(Route<STATE>),
+}
+
+type GuardFn<STATE> = dyn Fn(&Navigation<STATE>) -> GuardDecision<STATE>;
+
+/// A function that decides whether a navigation may happen.
+pub struct NavigationGuard<STATE>(Rc<GuardFn<STATE>>);
+
+impl<STATE> NavigationGuard<STATE> {
+    /// Creates a guard from the function.
+    pub fn new<F>(guard: F) -> Self
+    where
+        F: Fn(&Navigation<STATE>) -> GuardDecision<STATE> + 'static,
+    {
+        NavigationGuard(Rc::new(guard))
+    }
+
+    /// Decides whether the navigation may happen.
+    pub fn check(&self, navigation: &Navigation<STATE>) -> GuardDecision<STATE> {
+        (self.0)(navigation)
+    }
+}
+
+impl<STATE> Clone for NavigationGuard<STATE> {
+    fn clone(&self) -> Self {
+        NavigationGuard(self.0.clone())
+    }
+}
+
+impl<STATE> Debug for NavigationGuard<STATE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("NavigationGuard")
+    }
+}
+
+/// The guards set by different owners, like the subscribers of the `RouteAgent`.
+///
+/// Guards are checked in the order they were first set.
+pub struct Guards<K, STATE> {
+    guards: Vec<(K, NavigationGuard<STATE>)>,
+}
+
+impl<K: PartialEq, STATE> Guards<K, STATE> {
+    /// Creates an empty set of guards.
+    pub fn new() -> Self {
+        Guards { guards: Vec::new() }
+    }
+
+    /// Sets the guard of the owner, replacing any guard it set before.
+    pub fn set(&mut self, owner: K, guard: NavigationGuard<STATE>) {
+        match self.guards.iter_mut().find(|(key, _)| *key == owner) {
+            Some((_, existing)) => *existing = guard,
+            None => self.guards.push((owner, guard)),
+        }
+    }
+
+    /// Removes the guard of the owner.
+    pub fn remove(&mut self, owner: &K) {
+        self.guards.retain(|(key, _)| key != owner);
+    }
+
+    /// Returns true if no guards are set.
+    pub fn is_empty(&self) -> bool {
+        self.guards.is_empty()
+    }
+
+    /// Decides whether the navigation may happen.
+    ///
+    /// The navigation is blocked if any guard blocks it.
+    /// When a guard redirects it, the guards after that one decide on the redirected navigation.
+    pub fn check(&self, navigation: &Navigation<STATE>) -> GuardDecision<STATE> {
+        let mut redirected: Option<Route<STATE>> = None;
+        for (_, guard) in &self.guards {
+            let decision = guard.check(&Navigation {
+                from: navigation.from,
+                to: redirected.as_ref().unwrap_or(navigation.to),
+                kind: navigation.kind,
+            });
+            match decision {
+                GuardDecision::Allow => {}
+                GuardDecision::Block => return GuardDecision::Block,
+                GuardDecision::Redirect(route) => redirected = Some(route),
+            }
+        }
+        redirected.map_or(GuardDecision::Allow, GuardDecision::Redirect)
+    }
+}
+
+impl<K: PartialEq, STATE> Default for Guards<K, STATE> {
+    fn default() -> Self {
+        Guards::new()
+    }
+}
+
+impl<K: Debug, STATE> Debug for Guards<K, STATE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.guards.iter().map(|(key, _)| key))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(guards: &Guards<u32, ()>, to: &str) -> GuardDecision<()> {
+        guards.check(&Navigation {
+            from: &Route::new_no_state("/"),
+            to: &Route::new_no_state(to),
+            kind: NavigationKind::Push,
+        })
+    }
+
+    fn block(prefix: &'static str) -> NavigationGuard<()> {
+        NavigationGuard::new(move |navigation| {
+            if navigation.to.starts_with(prefix) {
+                GuardDecision::Block
+            } else {
+                GuardDecision::Allow
+            }
+        })
+    }
+
+    fn redirect(from: &'static str, to: &'static str) -> NavigationGuard<()> {
+        NavigationGuard::new(move |navigation| {
+            if navigation.to.route == from {
+                GuardDecision::Redirect(Route::new_no_state(to))
+            } else {
+                GuardDecision::Allow
+            }
+        })
+    }
+
+    #[test]
+    fn navigation_is_allowed_without_guards() {
+        assert_eq!(check(&Guards::new(), "/about"), GuardDecision::Allow);
+    }
+
+    #[test]
+    fn any_guard_can_block() {
+        let mut guards = Guards::new();
+        guards.set(1, block("/admin"));
+        guards.set(2, block("/about"));
+        assert_eq!(check(&guards, "/about"), GuardDecision::Block);
+        assert_eq!(check(&guards, "/users"), GuardDecision::Allow);
+    }
+
+    #[test]
+    fn later_guards_check_redirects() {
+        let mut guards = Guards::new();
+        guards.set(1, redirect("/old", "/new"));
+        guards.set(2, redirect("/new", "/newer"));
+        assert_eq!(
+            check(&guards, "/old"),
+            GuardDecision::Redirect(Route::new_no_state("/newer"))
+        );
+
+        guards.set(2, block("/new"));
+        assert_eq!(check(&guards, "/old"), GuardDecision::Block);
+    }
+
+    #[test]
+    fn guards_are_replaced_and_removed_by_owner() {
+        let mut guards = Guards::new();
+        guards.set(1, block("/about"));
+        guards.set(1, block("/admin"));
+        assert_eq!(check(&guards, "/about"), GuardDecision::Allow);
+        guards.remove(&1);
+        assert!(guards.is_empty());
+    }
+}
@@ -0,0 +1,473 @@
+//! History backend that interfaces with the browser.
+
+use super::{HistoryBackend, HistoryListener};
+use crate::{
+    location::{base_path, normalize_base_path, routing_mode, RoutingMode},
+    route::{Route, RouteState},
+};
+use cfg_if::cfg_if;
+use cfg_match::cfg_match;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+
+cfg_if! {
+    if #[cfg(feature = "std_web")] {
+        use stdweb::{
+            js,
+            unstable::{TryFrom, TryInto},
+            web::{
+                event::{BeforeUnloadEvent, HashChangeEvent, IEvent, PopStateEvent},
+                window, EventListenerHandle, History, IEventTarget, Location,
+            },
+            Value,
+        };
+    } else if #[cfg(feature = "web_sys")] {
+        use web_sys::{BeforeUnloadEvent, History, Location, PopStateEvent};
+        use gloo::events::{EventListener, EventListenerOptions};
+        use wasm_bindgen::{JsValue as Value, JsCast};
+    }
+}
+
+/// A history backend that manipulates the browser's URL bar through the History API,
+/// and responds to browser events when users press 'forward' or 'back'.
+///
+/// Routes are kept in the path or the fragment of the url, depending on its `RoutingMode`.
+/// In history mode, they are kept under its base path.
+///
+/// The position of every entry pushed through it is kept in the entry's state,
+/// next to the state of the route, as `{"yew_router_position": 1, "yew_router_state": "..."}`.
+/// The state of the route is read from `yew_router_state` in that case, and from the whole
+/// state otherwise, so entries added by other code can still be read.
+#[derive(Debug)]
+pub struct BrowserHistory<STATE = ()> {
+    history: History,
+    location: Location,
+    mode: RoutingMode,
+    base_path: String,
+    #[cfg(feature = "std_web")]
+    event_listener: Option<EventListenerHandle>,
+    #[cfg(feature = "web_sys")]
+    event_listener: Option<EventListener>,
+    #[cfg(feature = "std_web")]
+    hash_change_listener: Option<EventListenerHandle>,
+    #[cfg(feature = "web_sys")]
+    hash_change_listener: Option<EventListener>,
+    #[cfg(feature = "std_web")]
+    before_unload_listener: Option<EventListenerHandle>,
+    #[cfg(feature = "web_sys")]
+    before_unload_listener: Option<EventListener>,
+    phantom_data: PhantomData<STATE>,
+}
+
+impl<STATE> Default for BrowserHistory<STATE>
+where
+    STATE: RouteState,
+{
+    fn default() -> Self {
+        BrowserHistory::<STATE>::new()
+    }
+}
+
+impl<T> BrowserHistory<T> {
+    /// Creates the history, keeping routes wherever `set_routing_mode` and `set_base_path`
+    /// specified.
+    pub fn new() -> BrowserHistory<T> {
+        BrowserHistory::with_mode(routing_mode())
+    }
+
+    /// Creates the history, keeping routes in the part of the url specified by the mode.
+    pub fn with_mode(mode: RoutingMode) -> BrowserHistory<T> {
+        BrowserHistory::with_settings(mode, &base_path())
+    }
+
+    /// Creates the history, keeping routes in the part of the url specified by the mode,
+    /// and under the base path in history mode.
+    pub fn with_settings(mode: RoutingMode, base_path: &str) -> BrowserHistory<T> {
+        let (history, location) = cfg_match! {
+            feature = "std_web" => ({
+                (
+                    window().history(),
+                    window().location().expect("browser does not support location API")
+                )
+            }),
+            feature = "web_sys" => ({
+                let window = web_sys::window().unwrap();
+                (
+                    window.history().expect("browser does not support history API"),
+                    window.location()
+                )
+            }),
+        };
+
+        // The entry the application was loaded at is the last one, unless the page was reloaded
+        // after going back, in which case its position was kept.
+        if entry_state(&history).0.is_none() {
+            let length = cfg_match! {
+                feature = "std_web" => history.len(),
+                feature = "web_sys" => history.length().unwrap_or(1),
+            };
+            let position = length.saturating_sub(1) as usize;
+            let state = get_state_string(&history).unwrap_or_default();
+            let state_string = entry_state_string(Some(position), state);
+            cfg_match! {
+                feature = "std_web" => ({
+                    let _ = history.replace_state(state_string, "", None);
+                }),
+                feature = "web_sys" => ({
+                    let _ = history.replace_state(&Value::from_str(&state_string), "");
+                }),
+            };
+        }
+
+        BrowserHistory {
+            history,
+            location,
+            mode,
+            base_path: normalize_base_path(base_path),
+            event_listener: None,
+            hash_change_listener: None,
+            before_unload_listener: None,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Gets the part of the url that routes are kept in.
+    pub fn mode(&self) -> RoutingMode {
+        self.mode
+    }
+
+    /// Gets the path that routes are kept under in history mode.
+    pub fn base_path(&self) -> &str {
+        &self.base_path
+    }
+
+    #[inline]
+    fn get_route_from_location(
+        location: &Location,
+        mode: RoutingMode,
+        base_path: &str,
+    ) -> String {
+        let path = location.pathname().unwrap();
+        let query = location.search().unwrap();
+        let fragment = location.hash().unwrap();
+        mode.route_from_url(base_path, &path, &query, &fragment)
+    }
+
+    /// Gets the path name of the current url.
+    ///
+    /// This includes the base path.
+    /// In hash mode, this is the path the application is hosted at rather than the route.
+    pub fn get_path(&self) -> String {
+        self.location.pathname().unwrap()
+    }
+
+    /// Gets the query string of the current url.
+    pub fn get_query(&self) -> String {
+        self.location.search().unwrap()
+    }
+
+    /// Gets the fragment of the current url.
+    pub fn get_fragment(&self) -> String {
+        self.location.hash().unwrap()
+    }
+}
+
+impl<STATE> HistoryBackend<STATE> for BrowserHistory<STATE>
+where
+    STATE: RouteState,
+{
+    /// The route should be a relative path that starts with a `/`.
+    /// In history mode, the base path is added in front of it.
+    fn push(&mut self, route: &str, state: STATE) {
+        let state_string: String = serde_json::to_string(&state).unwrap_or_else(|_| {
+            log::error!("Could not serialize state string");
+            "".to_string()
+        });
+        let position = self.position().map(|position| position + 1);
+        let state_string = entry_state_string(position, state_string);
+        let url = self.mode.href(&self.base_path, route);
+        cfg_match! {
+            feature = "std_web" => ({
+                self.history.push_state(state_string, "", Some(&url));
+            }),
+            feature = "web_sys" => ({
+                let _ = self.history.push_state_with_url(&Value::from_str(&state_string), "", Some(&url));
+            }),
+        };
+    }
+
+    fn replace(&mut self, route: &str, state: STATE) {
+        let state_string: String = serde_json::to_string(&state).unwrap_or_else(|_| {
+            log::error!("Could not serialize state string");
+            "".to_string()
+        });
+        let state_string = entry_state_string(self.position(), state_string);
+        let url = self.mode.href(&self.base_path, route);
+        cfg_match! {
+            feature = "std_web" => ({
+                let _ = self.history.replace_state(state_string, "", Some(&url));
+            }),
+            feature = "web_sys" => ({
+                let _ = self.history.replace_state_with_url(&Value::from_str(&state_string), "", Some(&url));
+            }),
+        };
+    }
+
+    fn current(&self) -> Route<STATE> {
+        let route_string =
+            Self::get_route_from_location(&self.location, self.mode, &self.base_path);
+        let state: STATE = entry_state(&self.history)
+            .1
+            .or_else(|| {
+                log::trace!("History state is empty");
+                None
+            })
+            .and_then(|state_string| -> Option<Option<STATE>>{
+                serde_json::from_str(&state_string)
+                    .ok()
+                    .or_else(|| {
+                        log::error!("Could not deserialize state string");
+                        None
+                    })
+            })
+            .and_then(std::convert::identity) // flatten
+            .unwrap_or_default();
+        Route {
+            route: route_string,
+            state,
+        }
+    }
+
+    /// The listener is called when a popstate event is fired,
+    /// such as when the forward or back buttons are pressed.
+    ///
+    /// In hash mode, it is also called when the fragment of the url changes,
+    /// for example when a plain link to `#/users/5` is followed.
+    fn listen(&mut self, listener: HistoryListener<STATE>) {
+        let listener: Rc<dyn Fn(Route<STATE>)> = Rc::from(listener);
+        let mode = self.mode;
+        let base_path = self.base_path.clone();
+        // Browsers may fire both a popstate and a hashchange event when the fragment changes.
+        // The route emitted for the popstate is kept, so the hashchange doesn't emit it again.
+        let popped_route: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+
+        let on_hash_change = {
+            let listener = listener.clone();
+            let popped_route = popped_route.clone();
+            let base_path = base_path.clone();
+            move || {
+                let route: String =
+                    Self::get_route_from_location(&current_location(), mode, &base_path);
+                if popped_route.borrow_mut().take().as_ref() == Some(&route) {
+                    return;
+                }
+                // The fragment was changed without the History API, so there is no state.
+                listener(Route {
+                    route,
+                    state: STATE::default(),
+                })
+            }
+        };
+
+        let cb = move |event: PopStateEvent| {
+            let state_value: Value = event.state();
+            let state_string: String = cfg_match! {
+                feature = "std_web" => String::try_from(state_value).unwrap_or_default(),
+                feature = "web_sys" => state_value.as_string().unwrap_or_default(),
+            };
+            let state_string = split_entry_state(state_string).1.unwrap_or_default();
+            let state: STATE = serde_json::from_str(&state_string).unwrap_or_else(|_| {
+                log::error!("Could not deserialize state string");
+                STATE::default()
+            });
+
+            // Can't use the existing location, because this is a callback, and can't move it in
+            // here.
+            let route: String =
+                Self::get_route_from_location(&current_location(), mode, &base_path);
+            if mode == RoutingMode::Hash {
+                *popped_route.borrow_mut() = Some(route.clone());
+            }
+
+            listener(Route { route, state })
+        };
+
+        cfg_if! {
+            if #[cfg(feature = "std_web")] {
+                self.event_listener = Some(window().add_event_listener(move |event: PopStateEvent| {
+                    cb(event)
+                }));
+                if mode == RoutingMode::Hash {
+                    self.hash_change_listener = Some(window().add_event_listener(move |_: HashChangeEvent| {
+                        on_hash_change()
+                    }));
+                }
+            } else if #[cfg(feature = "web_sys")] {
+                self.event_listener = Some(EventListener::new(web_sys::window().unwrap().as_ref(), "popstate", move |event| {
+                    let event: PopStateEvent = event.clone().dyn_into().unwrap();
+                    cb(event)
+                }));
+                if mode == RoutingMode::Hash {
+                    self.hash_change_listener = Some(EventListener::new(web_sys::window().unwrap().as_ref(), "hashchange", move |_| {
+                        on_hash_change()
+                    }));
+                }
+            }
+        };
+    }
+
+    /// The browser navigates asynchronously, so the listener is called after this returns.
+    fn go(&mut self, delta: i32) {
+        cfg_match! {
+            feature = "std_web" => ({
+                let _ = self.history.go(delta);
+            }),
+            feature = "web_sys" => ({
+                let _ = self.history.go_with_delta(delta);
+            }),
+        };
+    }
+
+    /// The position is read from the state of the current entry.
+    /// It isn't known for entries that weren't added through this,
+    /// like those added by following a plain link to a fragment in hash mode.
+    fn position(&self) -> Option<usize> {
+        entry_state(&self.history).0
+    }
+
+    /// A `beforeunload` listener is added while users should be asked.
+    /// Browsers show their own message, and may not ask if the page wasn't interacted with.
+    fn prompt_before_unload(&mut self, prompt: bool) {
+        if !prompt {
+            cfg_match! {
+                feature = "std_web" => ({
+                    if let Some(listener) = self.before_unload_listener.take() {
+                        listener.remove();
+                    }
+                }),
+                feature = "web_sys" => ({
+                    self.before_unload_listener = None;
+                }),
+            };
+            return;
+        }
+        if self.before_unload_listener.is_some() {
+            return;
+        }
+        cfg_if! {
+            if #[cfg(feature = "std_web")] {
+                self.before_unload_listener = Some(window().add_event_listener(|event: BeforeUnloadEvent| {
+                    event.prevent_default();
+                    // Some browsers only ask if a return value is set.
+                    js! { @(no_return) @{event}.returnValue = ""; }
+                }));
+            } else if #[cfg(feature = "web_sys")] {
+                self.before_unload_listener = Some(EventListener::new_with_options(
+                    web_sys::window().unwrap().as_ref(),
+                    "beforeunload",
+                    EventListenerOptions::enable_prevent_default(),
+                    |event| {
+                        event.prevent_default();
+                        // Some browsers only ask if a return value is set.
+                        if let Some(event) = event.dyn_ref::<BeforeUnloadEvent>() {
+                            event.set_return_value("");
+                        }
+                    },
+                ));
+            }
+        };
+    }
+}
+
+/// Gets the location of the window.
+fn current_location() -> Location {
+    cfg_match! {
+        feature = "std_web" => window().location().unwrap(),
+        feature = "web_sys" => web_sys::window().unwrap().location(),
+    }
+}
+
+fn get_state(history: &History) -> Value {
+    cfg_match! {
+        feature = "std_web" => js!(
+            return @{history}.state;
+        ),
+        feature = "web_sys" => history.state().unwrap(),
+    }
+}
+
+fn get_state_string(history: &History) -> Option<String> {
+    cfg_match! {
+        feature = "std_web" => get_state(history).try_into().ok(),
+        feature = "web_sys" => get_state(history).as_string(),
+    }
+}
+
+/// The state of a history entry: the position of the entry, and the state of its route.
+///
+/// The keys are prefixed, and no others are allowed, so that the state of a route isn't
+/// mistaken for an entry state.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EntryState {
+    #[serde(rename = "yew_router_position")]
+    position: usize,
+    /// The state of the route, serialized as JSON.
+    #[serde(rename = "yew_router_state")]
+    state: String,
+}
+
+/// Serializes the state of an entry, leaving the state of the route as it is if the position
+/// isn't known.
+fn entry_state_string(position: Option<usize>, state: String) -> String {
+    match position {
+        Some(position) => serde_json::to_string(&EntryState { position, state })
+            .expect("An entry state can always be serialized"),
+        None => state,
+    }
+}
+
+/// Splits the state of an entry into its position and the state of its route.
+///
+/// Entries that weren't added through `BrowserHistory` only have the state of their route.
+fn split_entry_state(state: String) -> (Option<usize>, Option<String>) {
+    let (position, state) = match serde_json::from_str::<EntryState>(&state) {
+        Ok(entry) => (Some(entry.position), entry.state),
+        Err(_) => (None, state),
+    };
+    (position, Some(state).filter(|state| !state.is_empty()))
+}
+
+/// Gets the position and the state of the route of the current entry.
+fn entry_state(history: &History) -> (Option<usize>, Option<String>) {
+    get_state_string(history).map_or((None, None), split_entry_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_state_round_trips() {
+        let state_string = entry_state_string(Some(3), r#"{"id":1}"#.to_string());
+        assert_eq!(
+            split_entry_state(state_string),
+            (Some(3), Some(r#"{"id":1}"#.to_string()))
+        );
+        assert_eq!(
+            split_entry_state(entry_state_string(None, "null".to_string())),
+            (None, Some("null".to_string()))
+        );
+    }
+
+    #[test]
+    fn plain_states_are_route_states() {
+        assert_eq!(split_entry_state("".to_string()), (None, None));
+        assert_eq!(
+            split_entry_state("null".to_string()),
+            (None, Some("null".to_string()))
+        );
+        let state = r#"{"position":1,"state":"a"}"#.to_string();
+        assert_eq!(split_entry_state(state.clone()), (None, Some(state)));
+    }
+}
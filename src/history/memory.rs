@@ -0,0 +1,166 @@
+//! History backend that keeps routes in memory.
+
+use super::{HistoryBackend, HistoryListener};
+use crate::route::Route;
+use std::fmt::{self, Debug, Formatter};
+
+/// A history backend that keeps its entries in memory, without using the browser.
+///
+/// This can be used to test navigation with `cargo test` on the host.
+///
+/// # Example
+/// ```
+/// use yew_router::history::{HistoryBackend, MemoryHistory};
+/// let mut history: MemoryHistory = MemoryHistory::new("/");
+/// history.push("/users/5", ());
+/// history.go(-1);
+/// assert_eq!(history.current().route, "/");
+/// ```
+pub struct MemoryHistory<STATE = ()> {
+    entries: Vec<Route<STATE>>,
+    index: usize,
+    listener: Option<HistoryListener<STATE>>,
+}
+
+impl<STATE: Default> MemoryHistory<STATE> {
+    /// Creates a history with a single entry for the route.
+    pub fn new(route: &str) -> Self {
+        MemoryHistory {
+            entries: vec![Route::new_default_state(route)],
+            index: 0,
+            listener: None,
+        }
+    }
+}
+
+impl<STATE: Default> Default for MemoryHistory<STATE> {
+    fn default() -> Self {
+        MemoryHistory::new("/")
+    }
+}
+
+impl<STATE> MemoryHistory<STATE> {
+    /// Gets every entry in the history, from the oldest to the newest.
+    pub fn entries(&self) -> &[Route<STATE>] {
+        &self.entries
+    }
+
+    /// Gets the position of the current entry in `entries`.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<STATE> Debug for MemoryHistory<STATE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryHistory")
+            .field("entries", &self.entries.len())
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+impl<STATE: Clone> HistoryBackend<STATE> for MemoryHistory<STATE> {
+    fn push(&mut self, route: &str, state: STATE) {
+        self.entries.truncate(self.index + 1);
+        self.entries.push(Route {
+            route: route.to_string(),
+            state,
+        });
+        self.index = self.entries.len() - 1;
+    }
+
+    fn replace(&mut self, route: &str, state: STATE) {
+        self.entries[self.index] = Route {
+            route: route.to_string(),
+            state,
+        };
+    }
+
+    fn current(&self) -> Route<STATE> {
+        self.entries[self.index].clone()
+    }
+
+    fn listen(&mut self, listener: HistoryListener<STATE>) {
+        self.listener = Some(listener);
+    }
+
+    /// The listener is called before this returns.
+    fn go(&mut self, delta: i32) {
+        let index = self.index as i64 + i64::from(delta);
+        if delta == 0 || index < 0 || index >= self.entries.len() as i64 {
+            return;
+        }
+        self.index = index as usize;
+        if let Some(listener) = &self.listener {
+            listener(self.current());
+        }
+    }
+
+    fn position(&self) -> Option<usize> {
+        Some(self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn push_removes_forward_entries() {
+        let mut history: MemoryHistory = MemoryHistory::new("/");
+        history.push("/a", ());
+        history.push("/b", ());
+        history.go(-2);
+        history.push("/c", ());
+        let routes: Vec<&str> = history.entries().iter().map(|r| r.route.as_str()).collect();
+        assert_eq!(routes, vec!["/", "/c"]);
+        assert_eq!(history.index(), 1);
+        assert_eq!(history.position(), Some(1));
+    }
+
+    #[test]
+    fn replace_keeps_position() {
+        let mut history: MemoryHistory<u32> = MemoryHistory::new("/");
+        history.push("/a", 1);
+        history.replace("/b", 2);
+        assert_eq!(history.entries().len(), 2);
+        assert_eq!(
+            history.current(),
+            Route {
+                route: "/b".to_string(),
+                state: 2
+            }
+        );
+    }
+
+    #[test]
+    fn go_notifies_listener() {
+        let visited = Rc::new(RefCell::new(Vec::new()));
+        let mut history: MemoryHistory = MemoryHistory::new("/");
+        {
+            let visited = visited.clone();
+            history.listen(Box::new(move |route| visited.borrow_mut().push(route.route)));
+        }
+        history.push("/a", ());
+        history.go(-1);
+        history.go(1);
+        assert_eq!(*visited.borrow(), vec!["/".to_string(), "/a".to_string()]);
+    }
+
+    #[test]
+    fn go_out_of_range_does_nothing() {
+        let visited = Rc::new(RefCell::new(0));
+        let mut history: MemoryHistory = MemoryHistory::new("/");
+        {
+            let visited = visited.clone();
+            history.listen(Box::new(move |_| *visited.borrow_mut() += 1));
+        }
+        history.go(-1);
+        history.go(1);
+        history.go(0);
+        assert_eq!(history.current().route, "/");
+        assert_eq!(*visited.borrow(), 0);
+    }
+}
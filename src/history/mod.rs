@@ -0,0 +1,60 @@
+//! Backends that keep the stack of visited routes.
+//!
+//! The `RouteService` navigates through a `HistoryBackend`.
+//! `BrowserHistory` uses the browser's History API, while `MemoryHistory` keeps routes in memory,
+//! so navigation can be tested outside of a browser.
+use crate::route::Route;
+
+#[cfg(feature = "service")]
+mod browser;
+#[cfg(feature = "service")]
+pub use browser::BrowserHistory;
+
+mod memory;
+pub use memory::MemoryHistory;
+
+/// A function that is called with the new current route when a history is navigated through.
+pub type HistoryListener<STATE> = Box<dyn Fn(Route<STATE>)>;
+
+/// A stack of routes that can be navigated through, like the browser's session history.
+pub trait HistoryBackend<STATE> {
+    /// Adds an entry for the route after the current one and makes it current,
+    /// removing any entries that were after the current one.
+    fn push(&mut self, route: &str, state: STATE);
+
+    /// Replaces the current entry with one for the route.
+    fn replace(&mut self, route: &str, state: STATE);
+
+    /// Gets the route of the current entry.
+    fn current(&self) -> Route<STATE>;
+
+    /// Registers a listener that is called with the new current route when the history is
+    /// navigated through by `go`, or by the user.
+    /// It isn't called when routes are pushed or replaced.
+    ///
+    /// This replaces any listener that was registered before.
+    fn listen(&mut self, listener: HistoryListener<STATE>);
+
+    /// Moves through the history by the given number of entries,
+    /// backwards if the number is negative.
+    ///
+    /// Nothing happens if there is no entry that far away from the current one.
+    fn go(&mut self, delta: i32);
+
+    /// Gets the position of the current entry, counting from the first one,
+    /// or `None` if it isn't known.
+    ///
+    /// The `RouteAgent` uses this to go back to the entry that was left when users move through
+    /// the history and a guard blocks it.
+    /// It adds the route that was left back to the history instead if this is `None`,
+    /// which it is by default.
+    fn position(&self) -> Option<usize> {
+        None
+    }
+
+    /// Sets whether users are asked to confirm leaving the application,
+    /// like by closing the tab or following a link to another site.
+    ///
+    /// This does nothing by default, because only the browser can be left.
+    fn prompt_before_unload(&mut self, _prompt: bool) {}
+}
@@ -7,6 +7,8 @@
 //! Among them are:
 //! * RouteService - Hooks into the History API and listens to `PopStateEvent`s to respond to users
 //!   clicking the back/forwards buttons.
+//! * HistoryBackend - The stack of routes the RouteService navigates through. `BrowserHistory`
+//!   uses the History API, and `MemoryHistory` allows navigation to be tested outside a browser.
 //! * RouteAgent - A singleton agent that owns a RouteService that provides an easy place for other
 //!   components and agents to hook into it.
 //! * Switch - A trait/derive macro that allows specification of how enums or structs can be constructed
@@ -14,6 +16,8 @@
 //! * Router - A component connected to the RouteAgent, and is capable of resolving Routes to
 //! Switch implementors, so you can use them to render Html.
 //! * Route - A struct containing an the route string and state.
+//! * RoutingMode - Whether routes are kept in the path of the url, or in its fragment for
+//!   applications served by static file hosts.
 //! * RouteButton & RouteLink - Wrapper components around buttons and anchor tags respectively that
 //!   allow users to change the route.
 //!
@@ -40,6 +44,8 @@
 //!   "agent") will be included.
 //! * "agent" - If enabled, the RouteAgent and its associated types will be included.
 //! * "components" - If enabled, the accessory components will be made available.
+//! * "warp" - If enabled, the server helpers will provide a warp filter.
+//! * "actix-web" - If enabled, the server helpers will provide an actix-web guard.
 
 #![deny(
     missing_docs,
@@ -66,8 +72,20 @@ pub mod service;
 #[cfg(feature = "agent")]
 pub mod agent;
 
+pub mod guard;
+
+pub mod history;
+
+pub mod location;
+
+pub mod manifest;
+
 pub mod route;
 
+pub mod server;
+
+pub mod sitemap;
+
 #[cfg(feature = "components")]
 pub mod components;
 
@@ -113,7 +131,7 @@ pub mod prelude {
         route::Route,
         switch::{Routable, Switch},
     };
-    pub use yew_router_macro::Switch;
+    pub use yew_router_macro::{route, Switch};
 }
 
 pub use alias::*;
@@ -129,4 +147,4 @@ pub use crate::router::RouterState;
 
 pub mod switch;
 pub use switch::Switch;
-pub use yew_router_macro::Switch;
+pub use yew_router_macro::{route, Switch};
@@ -0,0 +1,229 @@
+//! Where routes are kept in the browser's url.
+use std::cell::{Cell, RefCell};
+
+/// Where in the browser's url the route is kept.
+///
+/// The mode used by the application is set with `set_routing_mode`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RoutingMode {
+    /// The route is the path, query and fragment of the url, like `/users/5`.
+    /// If a base path is set, it is removed from the path, so `/app/users/5` is the route
+    /// `/users/5` under the base path `/app`.
+    ///
+    /// The server has to respond to every route with the application.
+    History,
+    /// The route is kept in the fragment of the url, like `/#/users/5`.
+    ///
+    /// The server only has to respond to the path the application is hosted at,
+    /// so this works with static file hosts. The base path isn't used, because the path of
+    /// the url isn't changed when navigating.
+    Hash,
+}
+
+impl Default for RoutingMode {
+    fn default() -> Self {
+        RoutingMode::History
+    }
+}
+
+thread_local! {
+    static ROUTING_MODE: Cell<RoutingMode> = Cell::new(RoutingMode::default());
+    static BASE_PATH: RefCell<String> = RefCell::new(String::new());
+}
+
+/// Sets where routes are kept in the browser's url.
+///
+/// This should be called before the application is started,
+/// because the mode is read when the `RouteService` is created.
+/// `RouterAnchor`s read it when they produce their `href`.
+pub fn set_routing_mode(mode: RoutingMode) {
+    ROUTING_MODE.with(|routing_mode| routing_mode.set(mode))
+}
+
+/// Gets where routes are kept in the browser's url.
+pub fn routing_mode() -> RoutingMode {
+    ROUTING_MODE.with(Cell::get)
+}
+
+/// Sets the path the application is served under, like `/app`.
+///
+/// In history mode, the base path is removed from the url before a route is switched,
+/// and added to routes before they are set in the url or used as the `href` of a `RouterAnchor`,
+/// so `Switch` implementations don't have to repeat it.
+/// A trailing slash is ignored, and `/` or an empty string means there is no base path.
+///
+/// Like the routing mode, this should be set before the application is started.
+pub fn set_base_path(base_path: &str) {
+    let base_path = normalize_base_path(base_path);
+    BASE_PATH.with(|path| *path.borrow_mut() = base_path)
+}
+
+/// Gets the path the application is served under, without a trailing slash.
+///
+/// This is empty if there is no base path.
+pub fn base_path() -> String {
+    BASE_PATH.with(|path| path.borrow().clone())
+}
+
+/// Gets the url that navigates to the route with the application's routing mode and base path.
+pub fn href(route: &str) -> String {
+    routing_mode().href(&base_path(), route)
+}
+
+/// Gets the route from the path, query and fragment of a url,
+/// with the application's routing mode and base path.
+pub fn route_from_url(path: &str, query: &str, fragment: &str) -> String {
+    routing_mode().route_from_url(&base_path(), path, query, fragment)
+}
+
+impl RoutingMode {
+    /// Gets the url that navigates to the route, relative to the current document.
+    ///
+    /// The base path should not have a trailing slash, as returned by `base_path`.
+    ///
+    /// # Example
+    /// ```
+    /// use yew_router::location::RoutingMode;
+    /// assert_eq!(RoutingMode::History.href("", "/users/5"), "/users/5");
+    /// assert_eq!(RoutingMode::History.href("/app", "/users/5"), "/app/users/5");
+    /// assert_eq!(RoutingMode::Hash.href("/app", "/users/5"), "#/users/5");
+    /// ```
+    pub fn href(self, base_path: &str, route: &str) -> String {
+        match self {
+            RoutingMode::History => format!("{}{}", base_path, route),
+            RoutingMode::Hash => format!("#{}", route),
+        }
+    }
+
+    /// Gets the route from the path, query and fragment of a url.
+    ///
+    /// In history mode, a path that isn't under the base path is used as it is.
+    ///
+    /// # Note
+    /// This expects that all three already have their expected separators (?, #, etc)
+    pub fn route_from_url(
+        self,
+        base_path: &str,
+        path: &str,
+        query: &str,
+        fragment: &str,
+    ) -> String {
+        match self {
+            RoutingMode::History => {
+                let path = strip_base_path(base_path, path).unwrap_or_else(|| {
+                    log::warn!("The path {} is not under the base path {}", path, base_path);
+                    path
+                });
+                format_route_string(path, query, fragment)
+            }
+            RoutingMode::Hash => {
+                let route = fragment.strip_prefix('#').unwrap_or(fragment);
+                if route.is_empty() {
+                    "/".to_string()
+                } else {
+                    route.to_string()
+                }
+            }
+        }
+    }
+}
+
+/// Removes the base path from the path of a url.
+///
+/// `None` is returned if the path isn't under the base path.
+pub(crate) fn strip_base_path<'a>(base_path: &str, path: &'a str) -> Option<&'a str> {
+    if !path.starts_with(base_path) {
+        return None;
+    }
+    match &path[base_path.len()..] {
+        "" => Some("/"),
+        rest if rest.starts_with('/') => Some(rest),
+        _ => None,
+    }
+}
+
+/// Removes a trailing slash from the base path, and makes sure it starts with one.
+pub(crate) fn normalize_base_path(base_path: &str) -> String {
+    let base_path = base_path.trim_end_matches('/');
+    if base_path.is_empty() || base_path.starts_with('/') {
+        base_path.to_string()
+    } else {
+        format!("/{}", base_path)
+    }
+}
+
+/// Formats a path, query, and fragment into a string.
+///
+/// # Note
+/// This expects that all three already have their expected separators (?, #, etc)
+pub(crate) fn format_route_string(path: &str, query: &str, fragment: &str) -> String {
+    format!(
+        "{path}{query}{fragment}",
+        path = path,
+        query = query,
+        fragment = fragment
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_route_is_whole_url() {
+        assert_eq!(
+            RoutingMode::History.route_from_url("", "/users/5", "?tab=posts", "#top"),
+            "/users/5?tab=posts#top"
+        );
+    }
+
+    #[test]
+    fn hash_route_is_fragment() {
+        let fragment = "#/users/5?tab=posts#top";
+        assert_eq!(
+            RoutingMode::Hash.route_from_url("/app", "/app/", "?lang=en", fragment),
+            "/users/5?tab=posts#top"
+        );
+    }
+
+    #[test]
+    fn empty_hash_route_is_root() {
+        assert_eq!(RoutingMode::Hash.route_from_url("", "/", "", ""), "/");
+        assert_eq!(RoutingMode::Hash.route_from_url("", "/", "", "#"), "/");
+    }
+
+    #[test]
+    fn base_path_is_stripped() {
+        let route = |path| RoutingMode::History.route_from_url("/app", path, "?q=1", "");
+        assert_eq!(route("/app/users/5"), "/users/5?q=1");
+        assert_eq!(route("/app/"), "/?q=1");
+        assert_eq!(route("/app"), "/?q=1");
+        assert_eq!(route("/application"), "/application?q=1");
+        assert_eq!(route("/users/5"), "/users/5?q=1");
+    }
+
+    #[test]
+    fn base_path_is_normalized() {
+        assert_eq!(normalize_base_path("/app/"), "/app");
+        assert_eq!(normalize_base_path("app"), "/app");
+        assert_eq!(normalize_base_path("/"), "");
+        assert_eq!(normalize_base_path(""), "");
+    }
+
+    #[test]
+    fn href_uses_base_path() {
+        set_base_path("/app/");
+        assert_eq!(base_path(), "/app");
+        assert_eq!(href("/users/5"), "/app/users/5");
+        assert_eq!(route_from_url("/app/users/5", "", ""), "/users/5");
+        set_base_path("");
+    }
+
+    #[test]
+    fn routing_mode_can_be_set() {
+        assert_eq!(routing_mode(), RoutingMode::History);
+        set_routing_mode(RoutingMode::Hash);
+        assert_eq!(routing_mode(), RoutingMode::Hash);
+        set_routing_mode(RoutingMode::History);
+    }
+}
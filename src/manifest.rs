@@ -0,0 +1,123 @@
+//! Exports the routes of a `Switch` as a JSON manifest.
+//!
+//! The manifest lists the pattern of every variant, so that tools outside of the application,
+//! like CDN rewrite rules or link checkers, can be configured from the same routes.
+//! It is built from `Switch::route_info`, so only derived implementations have routes in it.
+//!
+//! # Example
+//! ```
+//! use yew_router::{manifest::RouteManifest, Switch};
+//! #[derive(Switch)]
+//! enum AppRoute {
+//!     #[to = "/users/{id}"]
+//!     User(u32),
+//!     #[to = "/!"]
+//!     Home,
+//! }
+//!
+//! let manifest = RouteManifest::of::<AppRoute>();
+//! assert_eq!(manifest.routes[0].pattern, "/users/{id}");
+//! assert_eq!(manifest.routes[0].captures, vec!["id".to_string()]);
+//! assert!(manifest.routes[1].exact);
+//! ```
+//!
+//! The manifest can be written during the build by a test in the application's crate:
+//! ```no_run
+//! # use yew_router::Switch;
+//! # #[derive(Switch)]
+//! # enum AppRoute {
+//! #     #[to = "/!"]
+//! #     Home,
+//! # }
+//! #[test]
+//! fn write_route_manifest() {
+//!     yew_router::manifest::write_manifest::<AppRoute, _>("target/routes.json").unwrap();
+//! }
+//! ```
+use crate::{
+    matcher::MatcherToken,
+    switch::{RouteInfo, Switch},
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+
+/// The routes of a `Switch`, in the order they are tried.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RouteManifest {
+    /// The route of every variant.
+    pub routes: Vec<ManifestRoute>,
+}
+
+/// The route of a variant of a `Switch`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ManifestRoute {
+    /// The name of the variant or struct.
+    pub variant: String,
+    /// The matcher string of the variant.
+    pub pattern: String,
+    /// The names of the captures in the pattern, sorted.
+    pub captures: Vec<String>,
+    /// Whether the pattern contains `!`, so that it only matches routes without anything
+    /// after it.
+    pub exact: bool,
+    /// The types of the fields of the variant, in the order they are declared.
+    pub field_types: Vec<String>,
+}
+
+impl RouteManifest {
+    /// Gets the manifest of the routes of the `Switch`.
+    pub fn of<SW: Switch>() -> Self {
+        RouteManifest {
+            routes: SW::route_info().iter().map(ManifestRoute::from).collect(),
+        }
+    }
+
+    /// Renders the manifest as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("A manifest can always be serialized")
+    }
+
+    /// Reads a manifest from JSON.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+impl<'a> From<&'a RouteInfo> for ManifestRoute {
+    fn from(info: &'a RouteInfo) -> Self {
+        let mut captures: Vec<String> = info
+            .capture_names()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+        captures.sort();
+        ManifestRoute {
+            variant: info.ident.to_string(),
+            pattern: info.matcher_string.to_string(),
+            captures,
+            exact: info.tokens().contains(&MatcherToken::End),
+            field_types: info.field_types.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+/// Writes the manifest of the routes of the `Switch` to the file as JSON,
+/// creating the directories it is in.
+///
+/// The file isn't touched if it already contains the manifest,
+/// so that tools watching it aren't triggered by every build.
+pub fn write_manifest<SW, P>(path: P) -> io::Result<()>
+where
+    SW: Switch,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let json = RouteManifest::of::<SW>().to_json();
+    if fs::read_to_string(path).ok().as_ref() == Some(&json) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, json)
+}
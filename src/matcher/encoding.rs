@@ -0,0 +1,81 @@
+//! Percent-encoding of captured values.
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use std::borrow::Cow;
+
+/// Characters that would interfere with capturing a value from a route,
+/// or that aren't allowed to appear in a URL.
+const CAPTURE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'+')
+    .add(b'/')
+    .add(b'<')
+    .add(b'=')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Percent-encodes a value, so that it can be written into a route and captured again as it was.
+pub fn percent_encode(value: &str) -> String {
+    utf8_percent_encode(value, CAPTURE).to_string()
+}
+
+/// Decodes a percent-encoded value.
+///
+/// If the decoded value wouldn't be valid UTF-8, the value is returned as it is.
+/// The value is borrowed unless it contains escape sequences.
+pub fn percent_decode(value: &str) -> Cow<'_, str> {
+    percent_decode_str(value)
+        .decode_utf8()
+        .unwrap_or(Cow::Borrowed(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_special_characters() {
+        assert_eq!(percent_encode("a/b?c d&e=f#g"), "a%2Fb%3Fc%20d%26e%3Df%23g");
+    }
+
+    #[test]
+    fn encode_unicode() {
+        assert_eq!(percent_encode("ü"), "%C3%BC");
+    }
+
+    #[test]
+    fn decode_round_trip() {
+        let value = "100% /path?q=ü&x";
+        assert_eq!(percent_decode(&percent_encode(value)), value);
+    }
+
+    #[test]
+    fn decode_invalid_utf8_is_unchanged() {
+        assert_eq!(percent_decode("%FF"), "%FF");
+    }
+
+    #[test]
+    fn decode_without_escapes_borrows() {
+        match percent_decode("lorem-ipsum") {
+            Cow::Borrowed(decoded) => assert_eq!(decoded, "lorem-ipsum"),
+            Cow::Owned(_) => panic!("should borrow"),
+        }
+    }
+
+    #[test]
+    fn decode_malformed_sequence_is_unchanged() {
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+}
@@ -1,23 +1,33 @@
 use crate::matcher::{
+    percent_decode,
     util::{consume_until, next_delimiter, tag_possibly_case_sensitive},
     Captures, MatcherSettings,
 };
 use log::trace;
 use nom::{
     bytes::complete::{is_not, tag},
-    combinator::map,
     error::ErrorKind,
     sequence::terminated,
     IResult,
 };
-use std::{iter::Peekable, slice::Iter};
-use yew_router_route_parser::{CaptureVariant, MatcherToken};
+use std::borrow::Cow;
+use yew_router_route_parser::{
+    CaptureConstraint, CaptureVariant, MatcherToken, QueryParam, QueryValue,
+};
 
 /// Allows abstracting over capturing into a HashMap (Captures) or a Vec.
+///
+/// Keys borrow from the tokens, and values borrow from the route where possible.
 trait CaptureCollection<'a> {
     fn new2() -> Self;
-    fn insert2(&mut self, key: &'a str, value: String);
+    fn insert2(&mut self, key: &'a str, value: Cow<'a, str>);
+    /// Records that an optional capture was absent.
+    fn skip2(&mut self);
     fn extend2(&mut self, other: Self);
+    /// Marks the captures made so far, so that later ones can be undone.
+    fn mark2(&self) -> usize;
+    /// Undoes the captures that the tokens made after the mark.
+    fn undo2(&mut self, mark: usize, tokens: &[MatcherToken]);
 }
 
 impl<'a> CaptureCollection<'a> for Captures<'a> {
@@ -25,34 +35,96 @@ impl<'a> CaptureCollection<'a> for Captures<'a> {
         Captures::new()
     }
 
-    fn insert2(&mut self, key: &'a str, value: String) {
+    fn insert2(&mut self, key: &'a str, value: Cow<'a, str>) {
         self.insert(key, value);
     }
 
+    fn skip2(&mut self) {}
+
     fn extend2(&mut self, other: Self) {
         self.extend(other)
     }
+
+    fn mark2(&self) -> usize {
+        0
+    }
+
+    /// Removes the keys of the tokens' captures, which are unique, so the mark isn't needed.
+    fn undo2(&mut self, _mark: usize, tokens: &[MatcherToken]) {
+        for token in tokens {
+            match token {
+                MatcherToken::Capture(capture) => {
+                    self.remove(capture_key(capture));
+                }
+                MatcherToken::Optional(group) => self.undo2(0, group),
+                MatcherToken::Query { params, .. } => {
+                    for param in params {
+                        if let QueryValue::Capture(capture) = &param.value {
+                            self.remove(capture_key(capture));
+                        }
+                    }
+                }
+                MatcherToken::Exact(_) | MatcherToken::OneOf(_) | MatcherToken::End => {}
+            }
+        }
+    }
 }
 
-impl<'a> CaptureCollection<'a> for Vec<String> {
+impl<'a> CaptureCollection<'a> for Vec<Cow<'a, str>> {
     fn new2() -> Self {
         Vec::new()
     }
 
-    fn insert2(&mut self, _key: &'a str, value: String) {
+    fn insert2(&mut self, _key: &'a str, value: Cow<'a, str>) {
         self.push(value)
     }
 
+    fn skip2(&mut self) {}
+
+    fn extend2(&mut self, other: Self) {
+        self.extend(other)
+    }
+
+    fn mark2(&self) -> usize {
+        self.len()
+    }
+
+    fn undo2(&mut self, mark: usize, _tokens: &[MatcherToken]) {
+        self.truncate(mark)
+    }
+}
+
+impl<'a> CaptureCollection<'a> for Vec<Option<Cow<'a, str>>> {
+    fn new2() -> Self {
+        Vec::new()
+    }
+
+    fn insert2(&mut self, _key: &'a str, value: Cow<'a, str>) {
+        self.push(Some(value))
+    }
+
+    fn skip2(&mut self) {
+        self.push(None)
+    }
+
     fn extend2(&mut self, other: Self) {
         self.extend(other)
     }
+
+    fn mark2(&self) -> usize {
+        self.len()
+    }
+
+    fn undo2(&mut self, mark: usize, _tokens: &[MatcherToken]) {
+        self.truncate(mark)
+    }
 }
 
 #[allow(clippy::trivially_copy_pass_by_ref)]
 pub(super) fn match_into_map<'a, 'b: 'a>(
     tokens: &'b [MatcherToken],
     settings: &'b MatcherSettings,
-) -> impl Fn(&'a str) -> IResult<&'a str, Captures<'b>> {
+) -> impl Fn(&'a str) -> IResult<&'a str, Captures<'a>> {
     move |i: &str| matcher_impl(tokens, *settings, i)
 }
 
@@ -60,141 +132,447 @@ pub(super) fn match_into_map<'a, 'b: 'a>(
 pub(super) fn match_into_vec<'a, 'b: 'a>(
     tokens: &'b [MatcherToken],
     settings: &'b MatcherSettings,
-) -> impl Fn(&'a str) -> IResult<&'a str, Vec<String>> {
+) -> impl Fn(&'a str) -> IResult<&'a str, Vec<Cow<'a, str>>> {
     move |i: &str| matcher_impl(tokens, *settings, i)
 }
 
-fn matcher_impl<'a, 'b: 'a, CAP: CaptureCollection<'b>>(
+/// Like `match_into_vec`, but every capture section gets a slot,
+/// with `None` standing in for captures within optional groups that were skipped
+/// and for optional query parameters that were absent.
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub(super) fn match_into_optional_vec<'a, 'b: 'a>(
+    tokens: &'b [MatcherToken],
+    settings: &'b MatcherSettings,
+) -> impl Fn(&'a str) -> IResult<&'a str, Vec<Option<Cow<'a, str>>>> {
+    move |i: &'a str| matcher_impl(tokens, *settings, i)
+}
+
+/// The tokens that are left to match, as optional groups are included or skipped.
+#[derive(Clone, Copy)]
+struct Remaining<'b> {
+    /// A capture that is matched once the token after it is known,
+    /// because that token determines where the capture ends.
+    capture: Option<&'b CaptureVariant>,
+    /// The number of captures within groups that were skipped after `capture`,
+    /// which are recorded as absent once it is matched.
+    skipped: usize,
+    /// The tokens that follow.
+    tokens: &'b [MatcherToken],
+    /// The tokens after the optional group that `tokens` is within.
+    after: &'b [MatcherToken],
+}
+
+fn matcher_impl<'a, 'b: 'a, CAP: CaptureCollection<'a>>(
     tokens: &'b [MatcherToken],
     settings: MatcherSettings,
-    mut i: &'a str,
+    i: &'a str,
 ) -> IResult<&'a str, CAP> {
     trace!("Attempting to match route: {:?} using: {:?}", i, tokens);
+    let remaining = Remaining {
+        capture: None,
+        skipped: 0,
+        tokens,
+        after: &[],
+    };
+    let mut captures = CAP::new2();
+    let i = match_remaining(remaining, settings, i, i, &mut captures)?;
+    trace!("Route Matched");
+    Ok((i, captures))
+}
 
-    let mut iter = tokens.iter().peekable();
-
-    let mut captures: CAP = CAP::new2();
+/// Matches the remaining tokens.
+///
+/// Optional groups are matched in place: a group is included if the rest of the route matches
+/// with it, and skipped otherwise, so groups are matched greedily.
+/// Likewise, the first alternative that the rest of the route matches after is used.
+/// Nothing is allocated apart from the captures themselves.
+fn match_remaining<'a, 'b: 'a, CAP: CaptureCollection<'a>>(
+    mut remaining: Remaining<'b>,
+    settings: MatcherSettings,
+    route: &'a str,
+    mut i: &'a str,
+    captures: &mut CAP,
+) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
+    loop {
+        if remaining.tokens.is_empty() {
+            remaining.tokens = std::mem::take(&mut remaining.after);
+        }
+        // Groups can't be nested, so a group is only ever found outside of one.
+        if let Some(MatcherToken::Optional(group)) = remaining.tokens.first() {
+            let after = &remaining.tokens[1..];
+            let mark = captures.mark2();
+            let with_group = Remaining {
+                tokens: group,
+                after,
+                ..remaining
+            };
+            if let Ok(ii) = match_remaining(with_group, settings, route, i, captures) {
+                return Ok(ii);
+            }
+            trace!("Skipping optional group: {:?}", group);
+            captures.undo2(mark, remaining.tokens);
+            let skipped = group.iter().filter(|token| token.is_capture()).count();
+            if remaining.capture.is_some() {
+                remaining.skipped += skipped;
+            } else {
+                (0..skipped).for_each(|_| captures.skip2());
+            }
+            remaining.tokens = after;
+            continue;
+        }
 
-    while let Some(token) = iter.next() {
+        let next = remaining.tokens.first();
+        if let Some(capture) = remaining.capture.take() {
+            i = match_capture(capture, next, settings, i, captures)?;
+            (0..remaining.skipped).for_each(|_| captures.skip2());
+            remaining.skipped = 0;
+            continue;
+        }
+        let token = match next {
+            Some(token) => token,
+            None => return Ok(i),
+        };
+        remaining.tokens = &remaining.tokens[1..];
         i = match token {
             MatcherToken::Exact(literal) => {
                 trace!("Matching '{}' against literal: '{}'", i, literal);
-                tag_possibly_case_sensitive(literal.as_str(), !settings.case_insensitive)(i)?.0
+                match_literal(literal, settings, i)?
+            }
+            MatcherToken::OneOf(alternatives) => {
+                trace!("Matching '{}' against alternatives: {:?}", i, alternatives);
+                return match_one_of(alternatives, remaining, settings, route, i, captures);
+            }
+            MatcherToken::Query { continued, params } => {
+                trace!("Matching '{}' against query: {:?}", i, params);
+                match_query(i, *continued, params, settings, captures)?
+            }
+            MatcherToken::Capture(capture) => {
+                remaining.capture = Some(capture);
+                i
             }
-            MatcherToken::Capture(capture) => match &capture {
-                CaptureVariant::Named(name) => capture_named(i, &mut iter, &name, &mut captures)?,
-                CaptureVariant::ManyNamed(name) => {
-                    capture_many_named(i, &mut iter, &name, &mut captures)?
-                }
-                CaptureVariant::NumberedNamed { sections, name } => {
-                    capture_numbered_named(i, &mut iter, Some((&name, &mut captures)), *sections)?
-                }
-                CaptureVariant::Unnamed => capture_named(i, &mut iter, "", &mut captures)?,
-                CaptureVariant::ManyUnnamed => capture_many_named(i, &mut iter, "", &mut captures)?,
-                CaptureVariant::NumberedUnnamed { sections } => {
-                    capture_numbered_named(i, &mut iter, Some(("", &mut captures)), *sections)?
-                }
-            },
             MatcherToken::End => {
-                if !i.is_empty() {
+                let consumed = &route[..route.len() - i.len()];
+                if settings.trailing_slash.is_ignored() && i == "/" && !consumed.ends_with('/') {
+                    trace!("Ignoring trailing slash");
+                    &i[1..]
+                } else if !i.is_empty() {
                     // this is approximately correct, but ultimately doesn't matter
                     return Err(nom::Err::Failure((i, ErrorKind::Eof)));
                 } else {
                     i
                 }
             }
+            MatcherToken::Optional(_) => unreachable!("Optional groups are matched beforehand"),
         };
     }
-    trace!("Route Matched");
+}
 
-    Ok((i, captures))
+/// Matches a capture section, which is ended by the token after it.
+fn match_capture<'a, 'b: 'a, CAP: CaptureCollection<'a>>(
+    capture: &'b CaptureVariant,
+    next: Option<&'b MatcherToken>,
+    settings: MatcherSettings,
+    i: &'a str,
+    captures: &mut CAP,
+) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
+    match capture {
+        CaptureVariant::Named(name) => capture_named(i, next, name, None, settings, captures),
+        CaptureVariant::ConstrainedNamed { name, constraint } => {
+            capture_named(i, next, name, Some(constraint), settings, captures)
+        }
+        CaptureVariant::ManyNamed(name) => capture_many_named(i, next, name, captures),
+        CaptureVariant::NumberedNamed { sections, name } => {
+            capture_numbered_named(i, next, Some((name, captures)), *sections, settings)
+        }
+        CaptureVariant::Unnamed => capture_named(i, next, "", None, settings, captures),
+        CaptureVariant::ManyUnnamed => capture_many_named(i, next, "", captures),
+        CaptureVariant::NumberedUnnamed { sections } => {
+            capture_numbered_named(i, next, Some(("", captures)), *sections, settings)
+        }
+    }
+}
+
+/// The key that a capture section is stored under.
+fn capture_key(capture: &CaptureVariant) -> &str {
+    match capture {
+        CaptureVariant::Named(name)
+        | CaptureVariant::ManyNamed(name)
+        | CaptureVariant::NumberedNamed { name, .. }
+        | CaptureVariant::ConstrainedNamed { name, .. } => name,
+        CaptureVariant::Unnamed
+        | CaptureVariant::ManyUnnamed
+        | CaptureVariant::NumberedUnnamed { .. } => "",
+    }
+}
+
+/// Matches a literal.
+///
+/// If trailing slashes are ignored, a literal ending with a slash also matches at the end of the
+/// path without it.
+fn match_literal<'a>(
+    literal: &'a str,
+    settings: MatcherSettings,
+    i: &'a str,
+) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
+    let is_sensitive = !settings.case_insensitive;
+    let error = match tag_possibly_case_sensitive(literal, is_sensitive)(i) {
+        Ok((i, _)) => return Ok(i),
+        Err(e) => e,
+    };
+    if settings.trailing_slash.is_ignored() && literal.ends_with('/') {
+        let without_slash = &literal[..literal.len() - 1];
+        if let Ok((ii, _)) = tag_possibly_case_sensitive(without_slash, is_sensitive)(i) {
+            if ii.is_empty() || ii.starts_with('?') || ii.starts_with('#') {
+                trace!("Ignoring missing trailing slash");
+                return Ok(ii);
+            }
+        }
+    }
+    Err(error)
+}
+
+/// Matches the first of the alternatives that the input starts with,
+/// and that the rest of the route matches after.
+fn match_one_of<'a, 'b: 'a, CAP: CaptureCollection<'a>>(
+    alternatives: &'b [String],
+    remaining: Remaining<'b>,
+    settings: MatcherSettings,
+    route: &'a str,
+    i: &'a str,
+    captures: &mut CAP,
+) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
+    let mark = captures.mark2();
+    for alternative in alternatives {
+        let is_sensitive = !settings.case_insensitive;
+        if let Ok((ii, _)) = tag_possibly_case_sensitive(alternative.as_str(), is_sensitive)(i) {
+            if let Ok(ii) = match_remaining(remaining, settings, route, ii, captures) {
+                return Ok(ii);
+            }
+            trace!("Backtracking from alternative: {:?}", alternative);
+            captures.undo2(mark, remaining.tokens);
+            captures.undo2(mark, remaining.after);
+        }
+    }
+    Err(nom::Err::Error((i, ErrorKind::Alt)))
 }
 
-fn capture_named<'a, 'b: 'a, CAP: CaptureCollection<'b>>(
+/// Matches a query section, treating its parameters as a set.
+///
+/// The parameters may appear in any order, and parameters that aren't specified are ignored.
+/// Many captures (`{*:name}`) collect every value of a repeated key, joined by `&`,
+/// and are absent if the key is, so that `key=` is captured as a single empty value.
+/// Optional parameters that are absent or empty are skipped,
+/// and if every parameter may be absent, the query may be absent entirely.
+/// The whole query is consumed, up until a `#` or the end of the input.
+fn match_query<'a, 'b: 'a, CAP: CaptureCollection<'a>>(
     i: &'a str,
-    iter: &mut Peekable<Iter<MatcherToken>>,
+    continued: bool,
+    params: &'b [QueryParam],
+    settings: MatcherSettings,
+    captures: &mut CAP,
+) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
+    let (query, remaining) = match tag(if continued { "&" } else { "?" })(i) {
+        Ok((query, _)) => query.split_at(query.find('#').unwrap_or(query.len())),
+        // A query made up of only parameters that may be absent can be left out entirely.
+        Err(_) if params.iter().all(QueryParam::may_be_absent) => ("", i),
+        Err(e) => return Err(e),
+    };
+
+    // The pairs are split again for every parameter, instead of being collected.
+    let pairs = || {
+        query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.find('=') {
+                Some(index) => (&pair[..index], &pair[index + 1..]),
+                None => (pair, ""),
+            })
+    };
+    let literal_eq = |a: &str, b: &str| {
+        if settings.case_insensitive {
+            a.chars()
+                .flat_map(char::to_lowercase)
+                .eq(b.chars().flat_map(char::to_lowercase))
+        } else {
+            a == b
+        }
+    };
+
+    for param in params {
+        let mut values = pairs()
+            .filter(|(key, _)| literal_eq(key, &param.key))
+            .map(|(_, value)| value);
+        match &param.value {
+            QueryValue::Exact(literal) => {
+                if !values.any(|value| literal_eq(value, literal)) {
+                    log::trace!("Query does not contain: {}={}", param.key, literal);
+                    return Err(nom::Err::Error((i, ErrorKind::Tag)));
+                }
+            }
+            QueryValue::Capture(capture) => {
+                let (capture_key, repeated, constraint) = match capture {
+                    CaptureVariant::Named(name) | CaptureVariant::NumberedNamed { name, .. } => {
+                        (name.as_str(), false, None)
+                    }
+                    CaptureVariant::ConstrainedNamed { name, constraint } => {
+                        (name.as_str(), false, Some(constraint))
+                    }
+                    CaptureVariant::ManyNamed(name) => (name.as_str(), true, None),
+                    CaptureVariant::Unnamed | CaptureVariant::NumberedUnnamed { .. } => {
+                        ("", false, None)
+                    }
+                    CaptureVariant::ManyUnnamed => ("", true, None),
+                };
+                if repeated {
+                    // Every value of the key is captured, including empty ones.
+                    // The values are left encoded, so that an encoded `&` can't be mistaken
+                    // for the separator between them.
+                    let first = match values.next() {
+                        Some(first) => first,
+                        None => {
+                            log::trace!("Repeated query parameter is absent: {}", param.key);
+                            captures.skip2();
+                            continue;
+                        }
+                    };
+                    let value = match values.next() {
+                        None => Cow::Borrowed(first),
+                        Some(second) => {
+                            let mut joined = [first, second].join("&");
+                            values.for_each(|value| {
+                                joined.push('&');
+                                joined.push_str(value);
+                            });
+                            Cow::Owned(joined)
+                        }
+                    };
+                    captures.insert2(capture_key, value);
+                    continue;
+                }
+                let value = values.next().unwrap_or("");
+                if value.is_empty() && param.optional {
+                    log::trace!("Optional query parameter is absent: {}", param.key);
+                    captures.skip2();
+                    continue;
+                }
+                if value.is_empty() {
+                    log::trace!("Query does not contain a value for: {}", param.key);
+                    return Err(nom::Err::Error((i, ErrorKind::Tag)));
+                }
+                let value = decode_capture(value, settings);
+                if let Some(constraint) = constraint {
+                    if !constraint.is_satisfied_by(&value) {
+                        log::trace!("Capture ({}) does not satisfy: {:?}", value, constraint);
+                        return Err(nom::Err::Error((i, ErrorKind::Verify)));
+                    }
+                }
+                captures.insert2(capture_key, value);
+            }
+        }
+    }
+    Ok(remaining)
+}
+
+/// Percent-decodes a captured value if the settings call for it.
+///
+/// The value is only copied if it had to be decoded.
+fn decode_capture(captured: &str, settings: MatcherSettings) -> Cow<'_, str> {
+    if settings.percent_encoding {
+        percent_decode(captured)
+    } else {
+        Cow::Borrowed(captured)
+    }
+}
+
+/// Captures a single section.
+///
+/// If a constraint is provided, the match fails if the decoded section doesn't satisfy it,
+/// allowing the next matcher to be tried instead.
+fn capture_named<'a, 'b: 'a, CAP: CaptureCollection<'a>>(
+    i: &'a str,
+    next: Option<&'b MatcherToken>,
     capture_key: &'b str,
+    constraint: Option<&CaptureConstraint>,
+    settings: MatcherSettings,
     matches: &mut CAP,
 ) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
     log::trace!("Matching Named ({})", capture_key);
-    if let Some(_peaked_next_token) = iter.peek() {
-        let delimiter = next_delimiter(iter);
-        let (ii, captured) = consume_until(delimiter)(i)?;
-        matches.insert2(capture_key, captured);
-        Ok(ii)
-    } else {
-        let (ii, captured) = map(valid_capture_characters, String::from)(i)?;
-        matches.insert2(capture_key, captured);
-        Ok(ii)
+    let (ii, captured) = match next {
+        Some(next @ MatcherToken::Exact(_)) | Some(next @ MatcherToken::OneOf(_)) => {
+            consume_until(next_delimiter(next))(i)?
+        }
+        _ => valid_capture_characters(i)?,
+    };
+    let captured = decode_capture(captured, settings);
+    if let Some(constraint) = constraint {
+        if !constraint.is_satisfied_by(&captured) {
+            log::trace!("Capture ({}) does not satisfy: {:?}", captured, constraint);
+            return Err(nom::Err::Error((i, ErrorKind::Verify)));
+        }
     }
+    matches.insert2(capture_key, captured);
+    Ok(ii)
 }
 
-fn capture_many_named<'a, 'b, CAP: CaptureCollection<'b>>(
+fn capture_many_named<'a, 'b: 'a, CAP: CaptureCollection<'a>>(
     i: &'a str,
-    iter: &mut Peekable<Iter<MatcherToken>>,
+    next: Option<&'b MatcherToken>,
     capture_key: &'b str,
     matches: &mut CAP,
 ) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
     log::trace!("Matching ManyUnnamed ({})", capture_key);
-    if let Some(_peaked_next_token) = iter.peek() {
-        let delimiter = next_delimiter(iter);
-        let (ii, captured) = consume_until(delimiter)(i)?;
-        matches.insert2(&capture_key, captured);
+    if let Some(next) = next {
+        let (ii, captured) = consume_until(next_delimiter(next))(i)?;
+        matches.insert2(capture_key, Cow::Borrowed(captured));
         Ok(ii)
     } else if i.is_empty() {
         // If the route string is empty, return an empty value.
-        matches.insert2(&capture_key, "".to_string());
+        matches.insert2(capture_key, Cow::Borrowed(i));
         Ok(i) // Match even if nothing is left
     } else {
-        let (ii, c) = map(valid_many_capture_characters, String::from)(i)?;
-        matches.insert2(&capture_key, c);
+        let (ii, c) = valid_many_capture_characters(i)?;
+        matches.insert2(capture_key, Cow::Borrowed(c));
         Ok(ii)
     }
 }
 
-fn capture_numbered_named<'a, 'b, CAP: CaptureCollection<'b>>(
+/// Captures a number of sections.
+///
+/// The sections are contiguous, so the value is a single slice of the input.
+fn capture_numbered_named<'a, 'b: 'a, CAP: CaptureCollection<'a>>(
     mut i: &'a str,
-    iter: &mut Peekable<Iter<MatcherToken>>,
+    next: Option<&'b MatcherToken>,
     name_and_captures: Option<(&'b str, &mut CAP)>,
     mut sections: usize,
+    settings: MatcherSettings,
 ) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
     log::trace!("Matching NumberedNamed ({})", sections);
-    let mut captured = "".to_string();
+    let start = i;
 
-    if let Some(_peaked_next_token) = iter.peek() {
+    if let Some(next @ MatcherToken::Exact(_)) | Some(next @ MatcherToken::OneOf(_)) = next {
         while sections > 0 {
             if sections > 1 {
-                let (ii, c) = terminated(valid_capture_characters, tag("/"))(i)?;
-                i = ii;
-                captured += c;
-                captured += "/";
+                i = terminated(valid_capture_characters, tag("/"))(i)?.0;
             } else {
-                let delimiter = next_delimiter(iter);
-                let (ii, c) = consume_until(delimiter)(i)?;
-                i = ii;
-                captured += &c;
+                i = consume_until(next_delimiter(next))(i)?.0;
             }
             sections -= 1;
         }
     } else {
         while sections > 0 {
             if sections > 1 {
-                let (ii, c) = terminated(valid_capture_characters, tag("/"))(i)?;
-                i = ii;
-                captured += c;
-                captured += "/";
+                i = terminated(valid_capture_characters, tag("/"))(i)?.0;
             } else {
                 // Don't consume the next character on the last section
-                let (ii, c) = valid_capture_characters(i)?;
-                i = ii;
-                captured += c;
+                i = valid_capture_characters(i)?.0;
             }
             sections -= 1;
         }
     }
 
     if let Some((name, captures)) = name_and_captures {
-        captures.insert2(&name, captured);
+        let captured = &start[..start.len() - i.len()];
+        captures.insert2(name, decode_capture(captured, settings));
     }
     Ok(i)
 }
@@ -216,7 +594,7 @@ mod integration_test {
 
     use yew_router_route_parser::{self, FieldNamingScheme};
 
-    use super::super::Captures;
+    use super::super::{Captures, TrailingSlash};
     //    use nom::combinator::all_consuming;
 
     #[test]
@@ -385,6 +763,304 @@ mod integration_test {
         matcher_impl::<Captures>(&x, settings, "/HeLLo").expect("should match");
     }
 
+    #[test]
+    fn constrained_capture() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/user/{id:u32}/{slug:[a-z-]+}",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (_, matches) = matcher_impl::<Captures>(&x, Default::default(), "/user/42/hello-there")
+            .expect("should match");
+        assert_eq!(matches["id"], "42".to_string());
+        assert_eq!(matches["slug"], "hello-there".to_string());
+
+        matcher_impl::<Captures>(&x, Default::default(), "/user/lorem/hello-there")
+            .expect_err("should not match");
+        matcher_impl::<Captures>(&x, Default::default(), "/user/42/Hello")
+            .expect_err("should not match");
+    }
+
+    #[test]
+    fn optional_group() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/posts[/{page}]!",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (_, matches) = matcher_impl::<Captures>(&x, Default::default(), "/posts/3")
+            .expect("should match");
+        assert_eq!(matches["page"], "3".to_string());
+        let (_, matches) =
+            matcher_impl::<Captures>(&x, Default::default(), "/posts").expect("should match");
+        assert!(matches.is_empty());
+        matcher_impl::<Captures>(&x, Default::default(), "/posts/3/4")
+            .expect_err("should not match");
+    }
+
+    #[test]
+    fn optional_group_into_optional_vec() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/{}[/{}]/edit",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let settings = MatcherSettings::default();
+        let (_, matches) = match_into_optional_vec(&x, &settings)("/lorem/edit")
+            .expect("should match");
+        assert_eq!(matches, vec![Some("lorem".into()), None]);
+        let (_, matches) = match_into_optional_vec(&x, &settings)("/lorem/ipsum/edit")
+            .expect("should match");
+        assert_eq!(
+            matches,
+            vec![Some("lorem".into()), Some("ipsum".into())]
+        );
+    }
+
+    #[test]
+    fn skipped_group_captures_are_undone() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/posts[/{page}/comments]",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (remaining, matches) =
+            matcher_impl::<Captures>(&x, Default::default(), "/posts/3/likes")
+                .expect("should match");
+        assert_eq!(remaining, "/3/likes");
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn consecutive_groups_into_optional_vec() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/{}[/v/{}][/page/{}]/edit",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let settings = MatcherSettings::default();
+        let (_, matches) = match_into_optional_vec(&x, &settings)("/lorem/page/2/edit")
+            .expect("should match");
+        assert_eq!(matches, vec![Some("lorem".into()), None, Some("2".into())]);
+        let (_, matches) = match_into_optional_vec(&x, &settings)("/lorem/v/ipsum/edit")
+            .expect("should match");
+        assert_eq!(matches, vec![Some("lorem".into()), Some("ipsum".into()), None]);
+    }
+
+    #[test]
+    fn query_in_any_order() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/path?a={a}&b={b}&c=lorem",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let route = "/path?c=lorem&b=2&other=3&a=1#frag";
+        let (remaining, matches) =
+            matcher_impl::<Captures>(&x, Default::default(), route).expect("should match");
+        assert_eq!(matches["a"], "1".to_string());
+        assert_eq!(matches["b"], "2".to_string());
+        assert_eq!(remaining, "#frag");
+    }
+
+    #[test]
+    fn query_missing_param() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/path?a={a}&b=lorem",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        matcher_impl::<Captures>(&x, Default::default(), "/path?a=1")
+            .expect_err("should not match");
+        matcher_impl::<Captures>(&x, Default::default(), "/path?b=lorem")
+            .expect_err("should not match");
+        matcher_impl::<Captures>(&x, Default::default(), "/path?a=&b=lorem")
+            .expect_err("should not match");
+    }
+
+    #[test]
+    fn optional_query_param() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/path?page={page?}&q={q}",
+            FieldNamingScheme::Named,
+        )
+        .expect("Should parse");
+        let (_, matches) = matcher_impl::<Captures>(&x, Default::default(), "/path?q=lorem")
+            .expect("should match");
+        assert_eq!(matches.get("page"), None);
+        let (_, matches) =
+            matcher_impl::<Captures>(&x, Default::default(), "/path?q=lorem&page=2")
+                .expect("should match");
+        assert_eq!(matches["page"], "2".to_string());
+        matcher_impl::<Captures>(&x, Default::default(), "/path?page=2")
+            .expect_err("should not match");
+    }
+
+    #[test]
+    fn optional_query_param_into_optional_vec() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/path?page={?}&q={}",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let settings = MatcherSettings::default();
+        let (_, matches) = match_into_optional_vec(&x, &settings)("/path?q=lorem")
+            .expect("should match");
+        assert_eq!(matches, vec![None, Some("lorem".into())]);
+    }
+
+    #[test]
+    fn repeated_query_param() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/path?tag={*:tags}",
+            FieldNamingScheme::Named,
+        )
+        .expect("Should parse");
+        let (_, matches) =
+            matcher_impl::<Captures>(&x, Default::default(), "/path?tag=a&q=lorem&tag=b")
+                .expect("should match");
+        assert_eq!(matches["tags"], "a&b".to_string());
+        let (_, matches) = matcher_impl::<Captures>(&x, Default::default(), "/path?tag=&tag=a")
+            .expect("should match");
+        assert_eq!(matches["tags"], "&a".to_string());
+        let (_, matches) = matcher_impl::<Captures>(&x, Default::default(), "/path?tag=")
+            .expect("should match");
+        assert_eq!(matches["tags"], "".to_string());
+        let (_, matches) = matcher_impl::<Vec<Option<Cow<str>>>>(&x, Default::default(), "/path")
+            .expect("should match");
+        assert_eq!(matches, vec![None]);
+    }
+
+    #[test]
+    fn percent_decoded_captures() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/search/{term}?q={q}",
+            FieldNamingScheme::Named,
+        )
+        .expect("Should parse");
+        let (_, matches) = matcher_impl::<Captures>(
+            &x,
+            Default::default(),
+            "/search/hello%20world?q=a%26b%3Dc",
+        )
+        .expect("should match");
+        assert_eq!(matches["term"], "hello world".to_string());
+        assert_eq!(matches["q"], "a&b=c".to_string());
+
+        let settings = MatcherSettings {
+            percent_encoding: false,
+            ..Default::default()
+        };
+        let (_, matches) = matcher_impl::<Captures>(&x, settings, "/search/hello%20world?q=a")
+            .expect("should match");
+        assert_eq!(matches["term"], "hello%20world".to_string());
+    }
+
+    #[test]
+    fn captures_borrow_from_route() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/{2:path}/{name}?q={q}",
+            FieldNamingScheme::Named,
+        )
+        .expect("Should parse");
+        let (_, matches) =
+            matcher_impl::<Captures>(&x, Default::default(), "/a/b/lorem%20ipsum?q=dolor")
+                .expect("should match");
+        match (&matches["path"], &matches["q"]) {
+            (Cow::Borrowed(path), Cow::Borrowed(q)) => {
+                assert_eq!(*path, "a/b");
+                assert_eq!(*q, "dolor");
+            }
+            _ => panic!("undecoded captures should borrow from the route"),
+        }
+        assert_eq!(matches["name"], "lorem ipsum");
+    }
+
+    #[test]
+    fn repeated_query_param_is_not_decoded() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/path?tag={*:tags}",
+            FieldNamingScheme::Named,
+        )
+        .expect("Should parse");
+        let (_, matches) =
+            matcher_impl::<Captures>(&x, Default::default(), "/path?tag=a%26b&tag=c")
+                .expect("should match");
+        assert_eq!(matches["tags"], "a%26b&c".to_string());
+    }
+
+    #[test]
+    fn one_of() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/(user|users|members)/{id}",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (_, matches) = matcher_impl::<Captures>(&x, Default::default(), "/users/42")
+            .expect("should match");
+        assert_eq!(matches["id"], "42".to_string());
+        let (_, matches) = matcher_impl::<Captures>(&x, Default::default(), "/members/42")
+            .expect("should match");
+        assert_eq!(matches["id"], "42".to_string());
+        matcher_impl::<Captures>(&x, Default::default(), "/admins/42")
+            .expect_err("should not match");
+    }
+
+    #[test]
+    fn overlapping_alternatives_backtrack() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/(a|ab)b[/{id}]!",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        matcher_impl::<Captures>(&x, Default::default(), "/ab").expect("should match");
+        let (_, matches) = matcher_impl::<Vec<Option<Cow<str>>>>(&x, Default::default(), "/abb/5")
+            .expect("should match");
+        assert_eq!(matches, vec![Some(Cow::Borrowed("5"))]);
+        matcher_impl::<Captures>(&x, Default::default(), "/abbb").expect_err("should not match");
+    }
+
+    #[test]
+    fn capture_before_one_of() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/{name}(.html|.htm)",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (_, matches) = matcher_impl::<Captures>(&x, Default::default(), "/index.htm")
+            .expect("should match");
+        assert_eq!(matches["name"], "index".to_string());
+    }
+
+    #[test]
+    fn trailing_slash_policy() {
+        let strict = MatcherSettings::default();
+        let ignore = MatcherSettings {
+            trailing_slash: TrailingSlash::Ignore,
+            ..Default::default()
+        };
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/user/{id}!",
+            FieldNamingScheme::Named,
+        )
+        .expect("Should parse");
+        matcher_impl::<Captures>(&x, strict, "/user/42/").expect_err("should not match");
+        let (remaining, matches) =
+            matcher_impl::<Captures>(&x, ignore, "/user/42/").expect("should match");
+        assert_eq!(remaining, "");
+        assert_eq!(matches["id"], "42");
+
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/about/!",
+            FieldNamingScheme::Unit,
+        )
+        .expect("Should parse");
+        matcher_impl::<Captures>(&x, strict, "/about").expect_err("should not match");
+        matcher_impl::<Captures>(&x, ignore, "/about").expect("should match");
+        matcher_impl::<Captures>(&x, ignore, "/about/").expect("should match");
+        matcher_impl::<Captures>(&x, ignore, "/aboutx").expect_err("should not match");
+        matcher_impl::<Captures>(&x, ignore, "/about//").expect_err("should not match");
+    }
+
     #[test]
     fn end_token() {
         let x = yew_router_route_parser::parse_str_and_optimize_tokens(
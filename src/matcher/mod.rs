@@ -1,14 +1,27 @@
 //! Module for matching route strings based on tokens generated from the yew_router_route_parser
 //! crate.
 
+mod encoding;
 mod matcher_impl;
+mod prefix_trie;
+mod trailing_slash;
 mod util;
 
 use nom::IResult;
-use std::collections::HashSet;
-use yew_router_route_parser::{parse_str_and_optimize_tokens, PrettyParseError};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+};
+use yew_router_route_parser::{parse_str_and_optimize_tokens, DisplayTokens, PrettyParseError};
 
-pub use yew_router_route_parser::{CaptureVariant, Captures, MatcherToken};
+pub use encoding::{percent_decode, percent_encode};
+pub use prefix_trie::{Candidates, PrefixTrie};
+pub use trailing_slash::{canonical_trailing_slash, TrailingSlash};
+pub use yew_router_route_parser::{
+    CaptureConstraint, CaptureVariant, Captures, MatcherToken, PrimitiveType, QueryParam,
+    QueryValue,
+};
 
 /// Attempts to match routes, transform the route to Component props and render that Component.
 #[derive(Debug, PartialEq, Clone)]
@@ -24,16 +37,38 @@ pub struct RouteMatcher {
 pub struct MatcherSettings {
     /// All literal matches do not care about case.
     pub case_insensitive: bool,
+    /// Captured values are percent-decoded when matching,
+    /// and values are percent-encoded when building routes.
+    ///
+    /// Many captures (`{*}`) are left as they are,
+    /// because they are usually matched again by a nested `Switch`.
+    pub percent_encoding: bool,
+    /// How a slash at the end of the route's path is treated.
+    ///
+    /// Unless this is `Strict`, a route ending with a slash satisfies the end token (`!`),
+    /// and a literal ending with a slash matches at the end of the path without it.
+    pub trailing_slash: TrailingSlash,
 }
 
 impl Default for MatcherSettings {
     fn default() -> Self {
         MatcherSettings {
             case_insensitive: false,
+            percent_encoding: true,
+            trailing_slash: TrailingSlash::Strict,
         }
     }
 }
 
+impl Display for RouteMatcher {
+    /// Renders the matcher string that the tokens would be parsed from.
+    ///
+    /// The settings aren't part of the matcher string, so they aren't displayed.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        DisplayTokens(&self.tokens).fmt(f)
+    }
+}
+
 impl RouteMatcher {
     /// Attempt to create a RouteMatcher from a "matcher string".
     pub fn try_from(i: &str) -> Result<Self, PrettyParseError> {
@@ -64,10 +99,21 @@ impl RouteMatcher {
     pub fn capture_route_into_vec<'a, 'b: 'a>(
         &'b self,
         i: &'a str,
-    ) -> IResult<&'a str, Vec<String>> {
+    ) -> IResult<&'a str, Vec<Cow<'a, str>>> {
         matcher_impl::match_into_vec(&self.tokens, &self.settings)(i)
     }
 
+    /// Match a route string, collecting the results into a vector with a slot for every capture
+    /// section.
+    ///
+    /// Captures within optional groups that weren't present in the route string are `None`.
+    pub fn capture_route_into_optional_vec<'a, 'b: 'a>(
+        &'b self,
+        i: &'a str,
+    ) -> IResult<&'a str, Vec<Option<Cow<'a, str>>>> {
+        matcher_impl::match_into_optional_vec(&self.tokens, &self.settings)(i)
+    }
+
     /// Gets a set of all names that will be captured.
     /// This is useful in determining if a given struct will be able to be populated by a given path
     /// matcher before being given a concrete path to match.
@@ -77,21 +123,37 @@ impl RouteMatcher {
                 .iter()
                 .fold(HashSet::new(), |mut acc: HashSet<&str>, token| {
                     match token {
-                        MatcherToken::Exact(_) | MatcherToken::End => {}
-                        MatcherToken::Capture(capture) => match &capture {
-                            CaptureVariant::ManyNamed(name)
-                            | CaptureVariant::Named(name)
-                            | CaptureVariant::NumberedNamed { name, .. } => {
-                                acc.insert(&name);
+                        MatcherToken::Exact(_) | MatcherToken::OneOf(_) | MatcherToken::End => {}
+                        MatcherToken::Optional(group) => acc.extend(capture_names_impl(group)),
+                        MatcherToken::Query { params, .. } => {
+                            params.iter().for_each(|param| {
+                                if let QueryValue::Capture(capture) = &param.value {
+                                    if let Some(name) = capture_name(capture) {
+                                        acc.insert(name);
+                                    }
+                                }
+                            });
+                        }
+                        MatcherToken::Capture(capture) => {
+                            if let Some(name) = capture_name(capture) {
+                                acc.insert(name);
                             }
-                            CaptureVariant::Unnamed
-                            | CaptureVariant::ManyUnnamed
-                            | CaptureVariant::NumberedUnnamed { .. } => {}
-                        },
+                        }
                     }
                     acc
                 })
         }
+        fn capture_name(capture: &CaptureVariant) -> Option<&str> {
+            match capture {
+                CaptureVariant::ManyNamed(name)
+                | CaptureVariant::Named(name)
+                | CaptureVariant::NumberedNamed { name, .. }
+                | CaptureVariant::ConstrainedNamed { name, .. } => Some(name),
+                CaptureVariant::Unnamed
+                | CaptureVariant::ManyUnnamed
+                | CaptureVariant::NumberedUnnamed { .. } => None,
+            }
+        }
         capture_names_impl(&self.tokens)
     }
 }
@@ -277,4 +339,10 @@ mod tests {
             "garbage1/garbage2/garbage3".to_string()
         )
     }
+
+    #[test]
+    fn display_matcher_string() {
+        let matcher = RouteMatcher::try_from("/a/{b}?c={d}&e={f?}#g").expect("should parse");
+        assert_eq!(matcher.to_string(), "/a/{b}?c={d}&e={f?}#g");
+    }
 }
@@ -0,0 +1,120 @@
+//! Radix trie used to dispatch a route to the matchers that could match it.
+
+/// A radix trie over the literals that matchers begin with.
+///
+/// This allows the matchers that could possibly match a route to be found while reading the
+/// route once, instead of trying every matcher in turn.
+/// Matchers that don't begin with a literal are stored at the root, so they are always candidates.
+///
+/// Literals are compared without regard to ASCII case, so the trie can be used for matchers
+/// that are case insensitive as well as ones that aren't.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrefixTrie {
+    /// The literal that must follow the one leading to the parent node.
+    pub prefix: &'static str,
+    /// Indices of the matchers whose leading literal ends at this node.
+    pub items: &'static [usize],
+    /// Nodes that continue this one, none of which begin with the same character.
+    pub children: &'static [PrefixTrie],
+}
+
+impl PrefixTrie {
+    /// Gets the indices of the matchers whose leading literal begins the route, in ascending order.
+    pub fn candidates<'a>(&'a self, route: &'a str) -> Candidates<'a> {
+        Candidates {
+            items: self
+                .path(route)
+                .map(|node| node.items)
+                .filter(|items| !items.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Gets the nodes whose prefixes, joined from the root, begin the route.
+    fn path<'a>(&'a self, route: &'a str) -> impl Iterator<Item = &'a PrefixTrie> + 'a {
+        let mut next = Some((self, route));
+        std::iter::from_fn(move || {
+            let (node, rest) = next?;
+            next = node
+                .children
+                .iter()
+                .find(|child| {
+                    rest.len() >= child.prefix.len()
+                        && rest.as_bytes()[..child.prefix.len()]
+                            .eq_ignore_ascii_case(child.prefix.as_bytes())
+                })
+                .map(|child| (child, &rest[child.prefix.len()..]));
+            Some(node)
+        })
+    }
+}
+
+/// Iterator over the indices of the matchers that could match a route, in ascending order.
+///
+/// The trie is walked once, when the iterator is created.
+/// The items of each node along the route are already sorted, so they are merged as the
+/// iterator advances instead of being collected.
+#[derive(Debug, Clone)]
+pub struct Candidates<'a> {
+    /// The items of the nodes along the route that haven't been yielded yet.
+    items: Vec<&'a [usize]>,
+}
+
+impl<'a> Iterator for Candidates<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let (position, &candidate) = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(position, items)| Some((position, items.first()?)))
+            .min_by_key(|&(_, candidate)| candidate)?;
+        self.items[position] = &self.items[position][1..];
+        Some(candidate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TRIE: PrefixTrie = PrefixTrie {
+        prefix: "",
+        items: &[3],
+        children: &[PrefixTrie {
+            prefix: "/user",
+            items: &[2],
+            children: &[
+                PrefixTrie {
+                    prefix: "/new",
+                    items: &[0],
+                    children: &[],
+                },
+                PrefixTrie {
+                    prefix: "s",
+                    items: &[1],
+                    children: &[],
+                },
+            ],
+        }],
+    };
+
+    #[test]
+    fn candidates_along_the_route() {
+        assert_eq!(TRIE.candidates("/user/new").collect::<Vec<_>>(), vec![0, 2, 3]);
+        assert_eq!(TRIE.candidates("/users/42").collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(TRIE.candidates("/user").collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn root_items_are_always_candidates() {
+        assert_eq!(TRIE.candidates("").collect::<Vec<_>>(), vec![3]);
+        assert_eq!(TRIE.candidates("/posts").collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn prefixes_ignore_ascii_case() {
+        assert_eq!(TRIE.candidates("/USER/New").collect::<Vec<_>>(), vec![0, 2, 3]);
+    }
+}
@@ -0,0 +1,110 @@
+//! Policies for routes that differ from their matcher only by a trailing slash.
+
+/// How a slash at the end of a route's path is treated when matching.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TrailingSlash {
+    /// The route must end with a slash if and only if the matcher does.
+    Strict,
+    /// The route matches whether or not it ends with a slash.
+    Ignore,
+    /// Like `Ignore`, but the `Router` replaces the route with the one built from what it matched,
+    /// so that its trailing slash agrees with the matcher.
+    Redirect,
+}
+
+impl Default for TrailingSlash {
+    fn default() -> Self {
+        TrailingSlash::Strict
+    }
+}
+
+impl TrailingSlash {
+    /// Can a route match regardless of whether its path ends with a slash.
+    pub fn is_ignored(self) -> bool {
+        match self {
+            TrailingSlash::Strict => false,
+            TrailingSlash::Ignore | TrailingSlash::Redirect => true,
+        }
+    }
+}
+
+/// Gets the route with a trailing slash added to or removed from its path,
+/// so that it agrees with the canonical route.
+///
+/// `None` is returned if the paths already agree, or if they differ by more than a trailing slash.
+/// The paths are compared without regard to ASCII case, like the matchers the derive produces,
+/// so the canonical path replaces one that only differs from it by case as well.
+/// The query and fragment of the route are kept as they are.
+///
+/// # Example
+/// ```
+/// use yew_router::matcher::canonical_trailing_slash;
+/// assert_eq!(
+///     canonical_trailing_slash("/about/?q=1", "/about"),
+///     Some("/about?q=1".to_string())
+/// );
+/// assert_eq!(canonical_trailing_slash("/About/", "/about"), Some("/about".to_string()));
+/// assert_eq!(canonical_trailing_slash("/about", "/about"), None);
+/// assert_eq!(canonical_trailing_slash("/about/", "/contact"), None);
+/// ```
+pub fn canonical_trailing_slash(route: &str, canonical: &str) -> Option<String> {
+    let (path, rest) = route.split_at(end_of_path(route));
+    let canonical_path = &canonical[..end_of_path(canonical)];
+    if is_with_slash(path, canonical_path) || is_with_slash(canonical_path, path) {
+        Some(format!("{}{}", canonical_path, rest))
+    } else {
+        None
+    }
+}
+
+/// Is `a` the same as `b` followed by a slash, ignoring ASCII case.
+fn is_with_slash(a: &str, b: &str) -> bool {
+    a.len() == b.len() + 1
+        && a.as_bytes()[..b.len()].eq_ignore_ascii_case(b.as_bytes())
+        && a.ends_with('/')
+}
+
+/// The offset at which the query or fragment of a route begins.
+fn end_of_path(route: &str) -> usize {
+    route.find(&['?', '#'][..]).unwrap_or(route.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slash_is_removed() {
+        assert_eq!(
+            canonical_trailing_slash("/about/", "/about"),
+            Some("/about".to_string())
+        );
+    }
+
+    #[test]
+    fn slash_is_added_before_query_and_fragment() {
+        assert_eq!(
+            canonical_trailing_slash("/about?q=1#top", "/about/?q=2"),
+            Some("/about/?q=1#top".to_string())
+        );
+    }
+
+    #[test]
+    fn case_is_ignored() {
+        assert_eq!(
+            canonical_trailing_slash("/ABOUT/", "/about"),
+            Some("/about".to_string())
+        );
+        assert_eq!(
+            canonical_trailing_slash("/About", "/about/"),
+            Some("/about/".to_string())
+        );
+    }
+
+    #[test]
+    fn other_differences_are_left_alone() {
+        assert_eq!(canonical_trailing_slash("/About", "/about"), None);
+        assert_eq!(canonical_trailing_slash("/about//", "/about"), None);
+        assert_eq!(canonical_trailing_slash("/abouts", "/about"), None);
+    }
+}
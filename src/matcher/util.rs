@@ -1,13 +1,10 @@
 use nom::{
     bytes::complete::{tag, tag_no_case},
-    character::complete::anychar,
-    combinator::{cond, map, peek, rest},
-    error::{ErrorKind, ParseError},
-    multi::many_till,
+    combinator::{cond, map},
+    error::ErrorKind,
     sequence::pair,
     IResult,
 };
-use std::{iter::Peekable, rc::Rc, slice::Iter};
 use yew_router_route_parser::MatcherToken;
 
 /// Allows a configurable tag that can optionally be case insensitive.
@@ -24,61 +21,61 @@ pub fn tag_possibly_case_sensitive<'a, 'b: 'a>(
     )
 }
 
-/// Similar to alt, but works on a vector of tags.
-#[allow(unused)]
-pub fn alternative(alternatives: Vec<String>) -> impl Fn(&str) -> IResult<&str, &str> {
-    move |i: &str| {
-        for alternative in &alternatives {
-            if let done @ IResult::Ok(..) = tag(alternative.as_str())(i) {
-                return done;
-            }
+/// What terminates a forward search for the end of a capture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Delimiter<'b> {
+    /// A literal.
+    Tag(&'b str),
+    /// Any one of several literals.
+    OneOf(&'b [String]),
+    /// The end of the input.
+    End,
+}
+
+impl<'b> Delimiter<'b> {
+    /// Finds the offset of the earliest place in the input where the delimiter begins.
+    pub fn find(self, i: &str) -> Option<usize> {
+        match self {
+            Delimiter::Tag(sequence) => i.find(sequence),
+            Delimiter::OneOf(alternatives) => alternatives
+                .iter()
+                .filter_map(|alternative| i.find(alternative.as_str()))
+                .min(),
+            Delimiter::End => Some(i.len()),
         }
-        Err(nom::Err::Error((i, ErrorKind::Tag))) // nothing found.
     }
 }
 
-/// Consumes the input until the provided parser succeeds.
-/// The consumed input is returned in the form of an allocated string.
+/// Consumes the input until the delimiter is found.
+/// The consumed input is returned as a slice of the input.
 /// # Note
-/// `stop_parser` only peeks its input.
-pub fn consume_until<'a, F, E>(stop_parser: F) -> impl Fn(&'a str) -> IResult<&'a str, String, E>
-where
-    E: ParseError<&'a str>,
-    F: Fn(&'a str) -> IResult<&'a str, &'a str, E>,
-{
-    // In order for the returned fn to be Fn instead of FnOnce, wrap the inner fn in an RC.
-    let f = Rc::new(many_till(
-        anychar,
-        peek(stop_parser), // once this succeeds, stop folding.
-    ));
-    move |i: &str| {
-        let (i, (first, _stop)): (&str, (Vec<char>, &str)) = (f)(i)?;
-        let ret = first.into_iter().collect();
-        Ok((i, ret))
+/// The delimiter itself isn't consumed.
+pub fn consume_until<'a, 'b>(
+    delimiter: Delimiter<'b>,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> + 'b {
+    move |i: &'a str| match delimiter.find(i) {
+        Some(index) => Ok((&i[index..], &i[..index])),
+        None => Err(nom::Err::Error((&i[i.len()..], ErrorKind::Eof))),
     }
 }
 
-/// Produces a parser combinator that searches for the next possible set of strings of
-/// characters used to terminate a forward search.
+/// Gets the delimiter that terminates a forward search, from the token after a capture.
 ///
 /// # Panics
-/// This function assumes that the next item after a Capture must be an Exact.
+/// This function assumes that the next item after a Capture must be an Exact or a OneOf.
 /// If this is violated, this function will panic.
-pub fn next_delimiter<'a>(
-    iter: &mut Peekable<Iter<MatcherToken>>,
-) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
-    let t: MatcherToken = iter
-        .peek()
-        .copied()
-        .cloned()
-        .expect("There must be at least one token to peak in next_delimiter");
-
-    move |i: &'a str| match &t {
-        MatcherToken::Exact(sequence) => tag(sequence.as_str())(i),
-        MatcherToken::End => rest(i),
+pub fn next_delimiter(next: &MatcherToken) -> Delimiter<'_> {
+    match next {
+        MatcherToken::Exact(sequence) => Delimiter::Tag(sequence),
+        MatcherToken::OneOf(alternatives) => Delimiter::OneOf(alternatives),
+        MatcherToken::Query { continued, .. } => Delimiter::Tag(if *continued { "&" } else { "?" }),
+        MatcherToken::End => Delimiter::End,
         MatcherToken::Capture(_) => {
             panic!("underlying parser should not allow two captures in a row")
         }
+        MatcherToken::Optional(_) => {
+            panic!("optional groups should be resolved before searching for a delimiter")
+        }
     }
 }
 
@@ -88,40 +85,34 @@ mod tests {
 
     #[test]
     fn consume_until_simple() {
-        let parser = consume_until::<_, ()>(tag("z"));
+        let parser = consume_until(Delimiter::Tag("z"));
         let parsed = parser("abcz").expect("Should parse");
-        assert_eq!(parsed, ("z", "abc".to_string()))
+        assert_eq!(parsed, ("z", "abc"))
     }
 
     #[test]
     fn consume_until_fail() {
-        let parser = consume_until(tag("z"));
+        let parser = consume_until(Delimiter::Tag("z"));
         let e = parser("abc").expect_err("Should parse");
         assert_eq!(e, nom::Err::Error(("", ErrorKind::Eof)))
     }
 
     #[test]
-    fn alternative_simple() {
-        let parser = alternative(
-            vec!["c", "d", "abc"]
-                .into_iter()
-                .map(String::from)
-                .collect(),
-        );
-        let parsed = parser("abcz").expect("Should parse");
-        assert_eq!(parsed, ("z", "abc"))
+    fn consume_until_end() {
+        let parser = consume_until(Delimiter::End);
+        let parsed = parser("abc").expect("Should parse");
+        assert_eq!(parsed, ("", "abc"))
     }
 
     #[test]
-    fn alternative_and_consume_until() {
-        let parser = consume_until(alternative(
-            vec!["c", "d", "abc"]
-                .into_iter()
-                .map(String::from)
-                .collect(),
-        ));
+    fn one_of_and_consume_until() {
+        let alternatives: Vec<String> = vec!["c", "d", "abc"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let parser = consume_until(Delimiter::OneOf(&alternatives));
         let parsed = parser("first_stuff_abc").expect("should parse");
-        assert_eq!(parsed, ("abc", "first_stuff_".to_string()))
+        assert_eq!(parsed, ("abc", "first_stuff_"))
     }
 
     #[test]
@@ -3,6 +3,7 @@
 use crate::{
     agent::{RouteAgentBridge, RouteRequest},
     route::Route,
+    switch::trailing_slash_redirect,
     RouteState, Switch,
 };
 use std::{
@@ -99,6 +100,19 @@ where
     pub fn redirect<F: RedirectFn<SW, STATE> + 'static>(f: F) -> Option<Redirect<SW, STATE>> {
         Some(Redirect::new(f))
     }
+
+    /// Replaces the route in the browser with one whose trailing slash agrees with the route built
+    /// from the switch, if the switch uses `TrailingSlash::Redirect` and that is the only
+    /// difference between them.
+    fn replace_with_canonical_trailing_slash(&mut self, switch: &SW, route: Route<STATE>) {
+        if let Some(canonical) = trailing_slash_redirect(switch, &route.route) {
+            log::trace!("Redirecting route to its canonical trailing slash: {}", canonical);
+            self.router_agent.send(RouteRequest::ReplaceRouteNoBroadcast(Route {
+                route: canonical,
+                state: route.state,
+            }));
+        }
+    }
 }
 
 /// Message for Router.
@@ -210,6 +224,8 @@ where
                             ));
                         switch = Some(redirected)
                     }
+                } else if let Some(switched) = &switch {
+                    self.replace_with_canonical_trailing_slash(switched, route);
                 }
 
                 self.switch = switch;
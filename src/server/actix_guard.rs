@@ -0,0 +1,85 @@
+//! Adapter for actix-web.
+use super::{ClientRoutes, Resolution};
+use crate::Switch;
+use actix_web_dep::{dev::RequestHead, guard::Guard};
+use std::fmt::{self, Debug, Formatter};
+
+/// An actix-web guard that passes requests whose path and query resolve to a given `Resolution`.
+///
+/// A guard for `Resolution::Found` can serve the application with a 200 status,
+/// and one for `Resolution::NotFound` can serve it, or a dedicated 404 page, with a 404 status.
+pub struct ClientRouteGuard<SW> {
+    routes: ClientRoutes<SW>,
+    resolution: Resolution,
+}
+
+impl<SW: Switch> ClientRoutes<SW> {
+    /// Creates an actix-web guard that passes requests that resolve to the resolution.
+    pub fn actix_guard(self, resolution: Resolution) -> ClientRouteGuard<SW> {
+        ClientRouteGuard {
+            routes: self,
+            resolution,
+        }
+    }
+}
+
+impl<SW: Switch> Guard for ClientRouteGuard<SW> {
+    fn check(&self, request: &RequestHead) -> bool {
+        let path_and_query = request
+            .uri
+            .path_and_query()
+            .map(|path_and_query| path_and_query.as_str())
+            .unwrap_or_else(|| request.uri.path());
+        self.routes.resolve(path_and_query) == self.resolution
+    }
+}
+
+impl<SW> Clone for ClientRouteGuard<SW> {
+    fn clone(&self) -> Self {
+        ClientRouteGuard {
+            routes: self.routes.clone(),
+            resolution: self.resolution,
+        }
+    }
+}
+
+impl<SW> Debug for ClientRouteGuard<SW> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientRouteGuard")
+            .field("routes", &self.routes)
+            .field("resolution", &self.resolution)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::switch::LeadingSlash;
+    use actix_web_dep::test::TestRequest;
+
+    fn check(guard: &ClientRouteGuard<LeadingSlash<u32>>, uri: &str) -> bool {
+        guard.check(TestRequest::with_uri(uri).to_http_request().head())
+    }
+
+    #[test]
+    fn guard_checks_requests() {
+        let routes = ClientRoutes::<LeadingSlash<u32>>::new().exclude("/api");
+        let found = routes.clone().actix_guard(Resolution::Found);
+        let not_found = routes.actix_guard(Resolution::NotFound);
+        assert!(check(&found, "/5"));
+        assert!(!check(&not_found, "/5"));
+        assert!(check(&not_found, "/five?q=1"));
+        assert!(!check(&found, "/api/5"));
+        assert!(!check(&not_found, "/api/5"));
+    }
+
+    #[test]
+    fn guard_checks_requests_under_the_base_path() {
+        let found = ClientRoutes::<LeadingSlash<u32>>::new()
+            .base_path("/app")
+            .actix_guard(Resolution::Found);
+        assert!(check(&found, "/app/5"));
+        assert!(!check(&found, "/5"));
+    }
+}
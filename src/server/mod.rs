@@ -0,0 +1,167 @@
+//! Helpers for servers that serve the application.
+//!
+//! Because routes are resolved by the application, a server has to respond to every client route
+//! with the application's `index.html`.
+//! `ClientRoutes` decides whether a request path is one of the routes of a `Switch`, so that
+//! paths the application doesn't know can be answered with a 404 status instead of a 200,
+//! and paths reserved for the server, like `/api`, aren't answered with the application at all.
+//!
+//! Adapters for warp and actix-web are available with the "warp" and "actix-web" features.
+//!
+//! # Example
+//! ```
+//! use yew_router::{
+//!     server::{ClientRoutes, Resolution},
+//!     Switch,
+//! };
+//! #[derive(Switch)]
+//! enum AppRoute {
+//!     #[to = "/users/{id}"]
+//!     User(u32),
+//!     #[to = "/!"]
+//!     Home,
+//! }
+//!
+//! let routes = ClientRoutes::<AppRoute>::new().exclude("/api");
+//! assert_eq!(routes.resolve("/users/5"), Resolution::Found);
+//! assert_eq!(routes.resolve("/users/five"), Resolution::NotFound);
+//! assert_eq!(routes.resolve("/api/users/5"), Resolution::Excluded);
+//! ```
+use crate::{
+    location::{normalize_base_path, strip_base_path},
+    route::Route,
+    Switch,
+};
+use std::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+
+#[cfg(feature = "actix-web")]
+mod actix_guard;
+#[cfg(feature = "actix-web")]
+pub use actix_guard::ClientRouteGuard;
+
+#[cfg(feature = "warp")]
+mod warp_filter;
+
+/// How a request path resolves against the routes of the application.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Resolution {
+    /// The path is a route of the application, so the application should be served with a
+    /// 200 status.
+    Found,
+    /// The path isn't a route of the application.
+    /// The application, or a dedicated 404 page, should be served with a 404 status.
+    NotFound,
+    /// The path is reserved for the server, so the application shouldn't be served.
+    Excluded,
+}
+
+impl Resolution {
+    /// Gets the status code that the application should be served with,
+    /// or `None` if it shouldn't be served.
+    pub fn status_code(self) -> Option<u16> {
+        match self {
+            Resolution::Found => Some(200),
+            Resolution::NotFound => Some(404),
+            Resolution::Excluded => None,
+        }
+    }
+}
+
+/// Resolves request paths against the routes of a `Switch`.
+pub struct ClientRoutes<SW> {
+    excluded_prefixes: Vec<String>,
+    base_path: String,
+    switch: PhantomData<fn() -> SW>,
+}
+
+impl<SW: Switch> ClientRoutes<SW> {
+    /// Creates a resolver for the routes of the `Switch`, without any excluded paths.
+    pub fn new() -> Self {
+        ClientRoutes {
+            excluded_prefixes: Vec::new(),
+            base_path: String::new(),
+            switch: PhantomData,
+        }
+    }
+
+    /// Excludes paths that start with the prefix, like `/api`.
+    ///
+    /// The prefix has to match whole segments,
+    /// so `/api` excludes `/api` and `/api/users` but not `/apiary`.
+    pub fn exclude(mut self, prefix: &str) -> Self {
+        self.excluded_prefixes.push(normalize_base_path(prefix));
+        self
+    }
+
+    /// Sets the path the application is served under, like `/app`.
+    ///
+    /// The base path is removed from request paths before they are switched,
+    /// and paths that aren't under it are not found.
+    pub fn base_path(mut self, base_path: &str) -> Self {
+        self.base_path = normalize_base_path(base_path);
+        self
+    }
+
+    /// Resolves the path and query of a request.
+    pub fn resolve(&self, path_and_query: &str) -> Resolution {
+        let end_of_path = path_and_query
+            .find(&['?', '#'][..])
+            .unwrap_or(path_and_query.len());
+        let (path, rest) = path_and_query.split_at(end_of_path);
+        let excluded = self
+            .excluded_prefixes
+            .iter()
+            .any(|prefix| !prefix.is_empty() && strip_base_path(prefix, path).is_some());
+        if excluded {
+            return Resolution::Excluded;
+        }
+        let path = match strip_base_path(&self.base_path, path) {
+            Some(path) => path,
+            None => return Resolution::NotFound,
+        };
+        match SW::switch(Route::new_no_state(format!("{}{}", path, rest))) {
+            Some(_) => Resolution::Found,
+            None => Resolution::NotFound,
+        }
+    }
+}
+
+impl<SW: Switch> Default for ClientRoutes<SW> {
+    fn default() -> Self {
+        ClientRoutes::new()
+    }
+}
+
+impl<SW> Clone for ClientRoutes<SW> {
+    fn clone(&self) -> Self {
+        ClientRoutes {
+            excluded_prefixes: self.excluded_prefixes.clone(),
+            base_path: self.base_path.clone(),
+            switch: PhantomData,
+        }
+    }
+}
+
+impl<SW> Debug for ClientRoutes<SW> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientRoutes")
+            .field("excluded_prefixes", &self.excluded_prefixes)
+            .field("base_path", &self.base_path)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_codes() {
+        assert_eq!(Resolution::Found.status_code(), Some(200));
+        assert_eq!(Resolution::NotFound.status_code(), Some(404));
+        assert_eq!(Resolution::Excluded.status_code(), None);
+    }
+}
@@ -0,0 +1,43 @@
+//! Adapter for warp.
+use super::{ClientRoutes, Resolution};
+use crate::Switch;
+use warp::{path::FullPath, Filter, Rejection};
+
+impl<SW: Switch + 'static> ClientRoutes<SW> {
+    /// Creates a warp filter that extracts how the path and query of a request resolve.
+    ///
+    /// Excluded paths are rejected as not found, so they can be handled by other filters.
+    /// The resolution's `status_code` is the status to serve the application with.
+    pub fn warp_filter(self) -> impl Filter<Extract = (Resolution,), Error = Rejection> + Clone {
+        warp::path::full()
+            .and(warp::query::raw().or_else(|_| Ok::<_, Rejection>((String::new(),))))
+            .and_then(move |path: FullPath, query: String| {
+                let path_and_query = if query.is_empty() {
+                    path.as_str().to_string()
+                } else {
+                    format!("{}?{}", path.as_str(), query)
+                };
+                match self.resolve(&path_and_query) {
+                    Resolution::Excluded => Err(warp::reject::not_found()),
+                    resolution => Ok(resolution),
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::switch::LeadingSlash;
+
+    #[test]
+    fn filter_resolves_requests() {
+        let filter = ClientRoutes::<LeadingSlash<u32>>::new()
+            .exclude("/api")
+            .warp_filter();
+        let resolve = |path: &str| warp::test::request().path(path).filter(&filter);
+        assert_eq!(resolve("/5").unwrap(), Resolution::Found);
+        assert_eq!(resolve("/five?q=1").unwrap(), Resolution::NotFound);
+        assert!(resolve("/api/5").is_err());
+    }
+}
@@ -2,38 +2,24 @@
 
 use yew::callback::Callback;
 
-use crate::route::{Route, RouteState};
-use cfg_if::cfg_if;
-use cfg_match::cfg_match;
+use crate::{
+    history::{BrowserHistory, HistoryBackend},
+    location::RoutingMode,
+    route::{Route, RouteState},
+};
 use std::marker::PhantomData;
 
-cfg_if! {
-    if #[cfg(feature = "std_web")] {
-        use stdweb::{
-            js,
-            unstable::{TryFrom, TryInto},
-            web::{event::PopStateEvent, window, EventListenerHandle, History, IEventTarget, Location},
-            Value,
-        };
-    } else if #[cfg(feature = "web_sys")] {
-        use web_sys::{History, Location, PopStateEvent};
-        use gloo::events::EventListener;
-        use wasm_bindgen::{JsValue as Value, JsCast};
-    }
-}
-
 /// A service that facilitates manipulation of the browser's URL bar and responding to browser events
 /// when users press 'forward' or 'back'.
 ///
 /// The `T` determines what route state can be stored in the route service.
+///
+/// The service navigates through a `HistoryBackend`, which is the browser's history by default.
+/// Routes are kept in the path or the fragment of the url, depending on its `RoutingMode`.
+/// In history mode, they are kept under its base path.
 #[derive(Debug)]
-pub struct RouteService<STATE = ()> {
-    history: History,
-    location: Location,
-    #[cfg(feature = "std_web")]
-    event_listener: Option<EventListenerHandle>,
-    #[cfg(feature = "web_sys")]
-    event_listener: Option<EventListener>,
+pub struct RouteService<STATE = (), HISTORY = BrowserHistory<STATE>> {
+    history: HISTORY,
     phantom_data: PhantomData<STATE>,
 }
 
@@ -47,186 +33,140 @@ where
 }
 
 impl<T> RouteService<T> {
-    /// Creates the route service.
+    /// Creates the route service, keeping routes wherever `set_routing_mode` and `set_base_path`
+    /// specified.
     pub fn new() -> RouteService<T> {
-        let (history, location) = cfg_match! {
-            feature = "std_web" => ({
-                (
-                    window().history(),
-                    window().location().expect("browser does not support location API")
-                )
-            }),
-            feature = "web_sys" => ({
-                let window = web_sys::window().unwrap();
-                (
-                    window.history().expect("browser does not support history API"),
-                    window.location()
-                )
-            }),
-        };
+        RouteService::with_history(BrowserHistory::new())
+    }
 
-        RouteService {
-            history,
-            location,
-            event_listener: None,
-            phantom_data: PhantomData,
-        }
+    /// Creates the route service, keeping routes in the part of the url specified by the mode.
+    pub fn with_mode(mode: RoutingMode) -> RouteService<T> {
+        RouteService::with_history(BrowserHistory::with_mode(mode))
     }
 
-    #[inline]
-    fn get_route_from_location(location: &Location) -> String {
-        let path = location.pathname().unwrap();
-        let query = location.search().unwrap();
-        let fragment = location.hash().unwrap();
-        format_route_string(&path, &query, &fragment)
+    /// Creates the route service, keeping routes in the part of the url specified by the mode,
+    /// and under the base path in history mode.
+    pub fn with_settings(mode: RoutingMode, base_path: &str) -> RouteService<T> {
+        RouteService::with_history(BrowserHistory::with_settings(mode, base_path))
+    }
+
+    /// Gets the part of the url that routes are kept in.
+    pub fn mode(&self) -> RoutingMode {
+        self.history.mode()
+    }
+
+    /// Gets the path that routes are kept under in history mode.
+    pub fn base_path(&self) -> &str {
+        self.history.base_path()
     }
 
     /// Gets the path name of the current url.
+    ///
+    /// This includes the base path.
+    /// In hash mode, this is the path the application is hosted at rather than the route.
     pub fn get_path(&self) -> String {
-        self.location.pathname().unwrap()
+        self.history.get_path()
     }
 
     /// Gets the query string of the current url.
     pub fn get_query(&self) -> String {
-        self.location.search().unwrap()
+        self.history.get_query()
     }
 
     /// Gets the fragment of the current url.
     pub fn get_fragment(&self) -> String {
-        self.location.hash().unwrap()
+        self.history.get_fragment()
     }
 }
 
-impl<STATE> RouteService<STATE>
+impl<STATE, HISTORY> RouteService<STATE, HISTORY> {
+    /// Creates the route service, navigating through the given history.
+    pub fn with_history(history: HISTORY) -> RouteService<STATE, HISTORY> {
+        RouteService {
+            history,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Gets the history that the service navigates through.
+    pub fn history(&self) -> &HISTORY {
+        &self.history
+    }
+}
+
+impl<STATE, HISTORY> RouteService<STATE, HISTORY>
 where
-    STATE: RouteState,
+    STATE: 'static,
+    HISTORY: HistoryBackend<STATE>,
 {
     /// Registers a callback to the route service.
-    /// Callbacks will be called when the History API experiences a change such as
+    /// Callbacks will be called when the history experiences a change such as
     /// popping a state off of its stack when the forward or back buttons are pressed.
+    ///
+    /// In hash mode, they are also called when the fragment of the url changes,
+    /// for example when a plain link to `#/users/5` is followed.
     pub fn register_callback(&mut self, callback: Callback<Route<STATE>>) {
-        let cb = move |event: PopStateEvent| {
-            let state_value: Value = event.state();
-            let state_string: String = cfg_match! {
-                feature = "std_web" => String::try_from(state_value).unwrap_or_default(),
-                feature = "web_sys" => state_value.as_string().unwrap_or_default(),
-            };
-            let state: STATE = serde_json::from_str(&state_string).unwrap_or_else(|_| {
-                log::error!("Could not deserialize state string");
-                STATE::default()
-            });
-
-            // Can't use the existing location, because this is a callback, and can't move it in
-            // here.
-            let location: Location = cfg_match! {
-                feature = "std_web" => window().location().unwrap(),
-                feature = "web_sys" => web_sys::window().unwrap().location(),
-            };
-            let route: String = Self::get_route_from_location(&location);
-
-            callback.emit(Route { route, state })
-        };
-
-        cfg_if! {
-            if #[cfg(feature = "std_web")] {
-                self.event_listener = Some(window().add_event_listener(move |event: PopStateEvent| {
-                    cb(event)
-                }));
-            } else if #[cfg(feature = "web_sys")] {
-                self.event_listener = Some(EventListener::new(web_sys::window().unwrap().as_ref(), "popstate", move |event| {
-                    let event: PopStateEvent = event.clone().dyn_into().unwrap();
-                    cb(event)
-                }));
-            }
-        };
+        self.history
+            .listen(Box::new(move |route: Route<STATE>| callback.emit(route)));
     }
 
     /// Sets the browser's url bar to contain the provided route,
     /// and creates a history entry that can be navigated via the forward and back buttons.
     ///
     /// The route should be a relative path that starts with a `/`.
+    /// In history mode, the base path is added in front of it.
     pub fn set_route(&mut self, route: &str, state: STATE) {
-        let state_string: String = serde_json::to_string(&state).unwrap_or_else(|_| {
-            log::error!("Could not serialize state string");
-            "".to_string()
-        });
-        cfg_match! {
-            feature = "std_web" => ({
-                self.history.push_state(state_string, "", Some(route));
-            }),
-            feature = "web_sys" => ({
-                let _ = self.history.push_state_with_url(&Value::from_str(&state_string), "", Some(route));
-            }),
-        };
+        self.history.push(route, state);
     }
 
     /// Replaces the route with another one removing the most recent history event and
     /// creating another history event in its place.
     pub fn replace_route(&mut self, route: &str, state: STATE) {
-        let state_string: String = serde_json::to_string(&state).unwrap_or_else(|_| {
-            log::error!("Could not serialize state string");
-            "".to_string()
-        });
-        cfg_match! {
-            feature = "std_web" => ({
-                let _ = self.history.replace_state(state_string, "", Some(route));
-            }),
-            feature = "web_sys" => ({
-                let _ = self.history.replace_state_with_url(&Value::from_str(&state_string), "", Some(route));
-            }),
-        };
+        self.history.replace(route, state);
     }
 
     /// Gets the concatenated path, query, and fragment.
     pub fn get_route(&self) -> Route<STATE> {
-        let route_string = Self::get_route_from_location(&self.location);
-        let state: STATE = get_state_string(&self.history)
-            .or_else(|| {
-                log::trace!("History state is empty");
-                None
-            })
-            .and_then(|state_string| -> Option<Option<STATE>>{
-                serde_json::from_str(&state_string)
-                    .ok()
-                    .or_else(|| {
-                        log::error!("Could not deserialize state string");
-                        None
-                    })
-            })
-            .and_then(std::convert::identity) // flatten
-            .unwrap_or_default();
-        Route {
-            route: route_string,
-            state,
-        }
+        self.history.current()
     }
-}
 
-/// Formats a path, query, and fragment into a string.
-///
-/// # Note
-/// This expects that all three already have their expected separators (?, #, etc)
-pub(crate) fn format_route_string(path: &str, query: &str, fragment: &str) -> String {
-    format!(
-        "{path}{query}{fragment}",
-        path = path,
-        query = query,
-        fragment = fragment
-    )
-}
+    /// Moves through the history by the given number of entries,
+    /// backwards if the number is negative.
+    pub fn go(&mut self, delta: i32) {
+        self.history.go(delta);
+    }
 
-fn get_state(history: &History) -> Value {
-    cfg_match! {
-        feature = "std_web" => js!(
-            return @{history}.state;
-        ),
-        feature = "web_sys" => history.state().unwrap(),
+    /// Sets whether users are asked to confirm leaving the application,
+    /// like by closing the tab or following a link to another site.
+    pub fn prompt_before_unload(&mut self, prompt: bool) {
+        self.history.prompt_before_unload(prompt);
     }
 }
 
-fn get_state_string(history: &History) -> Option<String> {
-    cfg_match! {
-        feature = "std_web" => get_state(history).try_into().ok(),
-        feature = "web_sys" => get_state(history).as_string(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::MemoryHistory;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn navigates_through_memory_history() {
+        let mut service: RouteService<(), MemoryHistory> =
+            RouteService::with_history(MemoryHistory::new("/"));
+        let visited = Rc::new(RefCell::new(Vec::new()));
+        {
+            let visited = visited.clone();
+            service.register_callback(Callback::from(move |route: Route<()>| {
+                visited.borrow_mut().push(route.route)
+            }));
+        }
+
+        service.set_route("/users/5", ());
+        service.replace_route("/users/6", ());
+        assert_eq!(service.get_route().route, "/users/6");
+
+        service.go(-1);
+        assert_eq!(service.get_route().route, "/");
+        assert_eq!(*visited.borrow(), vec!["/".to_string()]);
     }
 }
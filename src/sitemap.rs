@@ -0,0 +1,117 @@
+//! Generates sitemaps from the routes of a `Switch`.
+//!
+//! # Example
+//! ```
+//! use yew_router::{sitemap, Switch};
+//! #[derive(Switch)]
+//! enum AppRoute {
+//!     #[to = "/users/{id}"]
+//!     User(u32),
+//!     #[to = "/about"]
+//!     About,
+//!     #[to = "/!"]
+//!     Home,
+//! }
+//!
+//! let users = || vec![AppRoute::User(1), AppRoute::User(2)];
+//! let xml = sitemap::sitemap_with::<AppRoute, _>("https://example.com/", &users);
+//! assert!(xml.contains("<loc>https://example.com/about</loc>"));
+//! assert!(xml.contains("<loc>https://example.com/</loc>"));
+//! assert!(xml.contains("<loc>https://example.com/users/2</loc>"));
+//! ```
+use crate::{route::Route, Switch};
+use std::fmt::Write;
+
+/// Supplies the values of a `Switch` whose routes have captures, so they can be enumerated.
+///
+/// This is implemented for closures that return a `Vec` of the values.
+pub trait RouteProvider<SW> {
+    /// Gets the values whose routes should be enumerated.
+    fn routes(&self) -> Vec<SW>;
+}
+
+impl<SW, F> RouteProvider<SW> for F
+where
+    F: Fn() -> Vec<SW>,
+{
+    fn routes(&self) -> Vec<SW> {
+        self()
+    }
+}
+
+/// Gets the routes of every value of the `Switch` without captures, followed by those supplied
+/// by the provider.
+///
+/// Routes that are built more than once are only included the first time.
+pub fn enumerate_routes<SW, P>(provider: &P) -> Vec<String>
+where
+    SW: Switch,
+    P: RouteProvider<SW> + ?Sized,
+{
+    let mut routes: Vec<String> = Vec::new();
+    for switch in SW::static_routes().into_iter().chain(provider.routes()) {
+        let route: Route<()> = switch.into();
+        if !routes.contains(&route.route) {
+            routes.push(route.route);
+        }
+    }
+    routes
+}
+
+/// Renders a sitemap of the routes of the `Switch` that don't have captures.
+///
+/// The base url is put in front of every route, without its trailing slash.
+pub fn sitemap<SW: Switch>(base_url: &str) -> String {
+    sitemap_with::<SW, _>(base_url, &Vec::new)
+}
+
+/// Renders a sitemap of the routes of the `Switch` that don't have captures,
+/// and the routes supplied by the provider.
+///
+/// The base url is put in front of every route, without its trailing slash.
+pub fn sitemap_with<SW, P>(base_url: &str, provider: &P) -> String
+where
+    SW: Switch,
+    P: RouteProvider<SW> + ?Sized,
+{
+    let base_url = base_url.trim_end_matches('/');
+    let mut xml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n"
+    ));
+    for route in enumerate_routes::<SW, P>(provider) {
+        let url = format!("{}{}", base_url, route);
+        let _ = writeln!(xml, "  <url><loc>{}</loc></url>", escape_xml(&url));
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// Escapes the characters that can't appear in the text of an XML element.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml_is_escaped() {
+        assert_eq!(
+            escape_xml("/a?b=<'c'>&d=\"e\""),
+            "/a?b=&lt;&apos;c&apos;&gt;&amp;d=&quot;e&quot;"
+        );
+    }
+}
@@ -1,6 +1,12 @@
 //! Parses routes into enums or structs.
-use crate::route::Route;
-use std::fmt::Write;
+use crate::{
+    matcher::{
+        canonical_trailing_slash, percent_encode, MatcherSettings, MatcherToken, RouteMatcher,
+        TrailingSlash,
+    },
+    route::Route,
+};
+use std::{collections::HashSet, fmt::Write};
 
 /// Alias to Switch.
 ///
@@ -59,6 +65,47 @@ pub trait Switch: Sized {
     /// Build part of a route from itself.
     fn build_route_section<STATE>(self, route: &mut String) -> Option<STATE>;
 
+    /// Build part of a route from itself, for a section that will be matched with the provided
+    /// settings.
+    ///
+    /// This allows values to be percent-encoded when the matcher will decode them,
+    /// whereas `build_route_section` writes them as they are.
+    /// Implementors that use their own matcher can rely on the default,
+    /// which ignores the settings.
+    fn build_route_section_with_settings<STATE>(
+        self,
+        route: &mut String,
+        settings: MatcherSettings,
+    ) -> Option<STATE> {
+        let _ = settings;
+        self.build_route_section(route)
+    }
+
+    /// The trailing slash policy of the matcher that this was switched from.
+    ///
+    /// The `Router` uses this to decide whether to replace a route with the canonical one built
+    /// from this.
+    /// Derived implementations return the policy set with `#[switch(trailing_slash = "...")]`.
+    fn trailing_slash(&self) -> TrailingSlash {
+        TrailingSlash::Strict
+    }
+
+    /// Every value that can be switched to from a route without captures.
+    ///
+    /// This is used to enumerate routes, for example to generate a sitemap.
+    /// Derived implementations return their unit variants, in the order they are tried.
+    fn static_routes() -> Vec<Self> {
+        Vec::new()
+    }
+
+    /// Describes the matcher of every variant, or of the struct.
+    ///
+    /// Derived implementations describe their variants in the order they are tried.
+    /// Implementations that don't use matchers return nothing.
+    fn route_info() -> Vec<RouteInfo> {
+        Vec::new()
+    }
+
     /// Called when the key (the named capture group) can't be located. Instead of failing outright,
     /// a default item can be provided instead.
     ///
@@ -72,6 +119,33 @@ pub trait Switch: Sized {
     }
 }
 
+/// Describes the matcher of a variant of a derived `Switch` enum, or of a derived struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteInfo {
+    /// The name of the variant or struct.
+    pub ident: &'static str,
+    /// The matcher string, as it was written in the `#[to]` attribute.
+    ///
+    /// `#[rest]` and `#[end]` attributes are included as `{*}` and `!`.
+    pub matcher_string: &'static str,
+    /// The matcher that routes are matched with.
+    pub matcher: RouteMatcher,
+    /// The types of the fields, in the order they are declared.
+    pub field_types: Vec<&'static str>,
+}
+
+impl RouteInfo {
+    /// The tokens that routes are matched with.
+    pub fn tokens(&self) -> &[MatcherToken] {
+        &self.matcher.tokens
+    }
+
+    /// The names of the captures in the matcher.
+    pub fn capture_names(&self) -> HashSet<&str> {
+        self.matcher.capture_names()
+    }
+}
+
 /// Wrapper that requires that an implementor of Switch must start with a `/`.
 ///
 /// This is needed for any non-derived type provided by yew-router to be used by itself.
@@ -95,6 +169,19 @@ impl<U: Switch> Switch for LeadingSlash<U> {
         write!(route, "/").ok()?;
         self.0.build_route_section(route)
     }
+
+    fn build_route_section_with_settings<T>(
+        self,
+        route: &mut String,
+        settings: MatcherSettings,
+    ) -> Option<T> {
+        write!(route, "/").ok()?;
+        self.0.build_route_section_with_settings(route, settings)
+    }
+
+    fn trailing_slash(&self) -> TrailingSlash {
+        self.0.trailing_slash()
+    }
 }
 
 /// Successfully match even when the captured section can't be found.
@@ -121,6 +208,24 @@ impl<U: Switch> Switch for Permissive<U> {
         }
     }
 
+    fn build_route_section_with_settings<STATE>(
+        self,
+        route: &mut String,
+        settings: MatcherSettings,
+    ) -> Option<STATE> {
+        if let Some(inner) = self.0 {
+            inner.build_route_section_with_settings(route, settings)
+        } else {
+            None
+        }
+    }
+
+    fn trailing_slash(&self) -> TrailingSlash {
+        self.0
+            .as_ref()
+            .map_or(TrailingSlash::Strict, Switch::trailing_slash)
+    }
+
     fn key_not_available() -> Option<Self> {
         Some(Permissive(None))
     }
@@ -159,6 +264,24 @@ impl<U: Switch + std::fmt::Debug> Switch for AllowMissing<U> {
             None
         }
     }
+
+    fn build_route_section_with_settings<STATE>(
+        self,
+        route: &mut String,
+        settings: MatcherSettings,
+    ) -> Option<STATE> {
+        if let AllowMissing(Some(inner)) = self {
+            inner.build_route_section_with_settings(route, settings)
+        } else {
+            None
+        }
+    }
+
+    fn trailing_slash(&self) -> TrailingSlash {
+        self.0
+            .as_ref()
+            .map_or(TrailingSlash::Strict, Switch::trailing_slash)
+    }
 }
 
 /// Builds a route from a switch.
@@ -174,6 +297,21 @@ fn build_route_from_switch<SW: Switch, STATE: Default>(switch: SW) -> Route<STAT
     Route { route: buf, state }
 }
 
+/// Gets the route that the `Router` replaces a route with after switching it to the value.
+///
+/// If the value uses `TrailingSlash::Redirect`, this is the route with its trailing slash made to
+/// agree with the route built from the value, when that is the only difference between them.
+pub(crate) fn trailing_slash_redirect<SW>(switched: &SW, route: &str) -> Option<String>
+where
+    SW: Switch + Clone,
+{
+    if switched.trailing_slash() != TrailingSlash::Redirect {
+        return None;
+    }
+    let canonical: Route<()> = build_route_from_switch(switched.clone());
+    canonical_trailing_slash(route, &canonical.route)
+}
+
 impl<SW: Switch, STATE: Default> From<SW> for Route<STATE> {
     fn from(switch: SW) -> Self {
         build_route_from_switch(switch)
@@ -190,6 +328,19 @@ impl<T: std::str::FromStr + std::fmt::Display> Switch for T {
         write!(route, "{}", self).expect("Writing to string should never fail.");
         None
     }
+
+    fn build_route_section_with_settings<U>(
+        self,
+        route: &mut String,
+        settings: MatcherSettings,
+    ) -> Option<U> {
+        if settings.percent_encoding {
+            route.push_str(&percent_encode(&self.to_string()));
+            None
+        } else {
+            self.build_route_section(route)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -203,6 +354,30 @@ mod test {
         assert_eq!(route, "/-432".to_string());
     }
 
+    #[test]
+    fn string_build_route_is_encoded() {
+        let mut route = "/".to_string();
+        "hello world/?"
+            .to_string()
+            .build_route_section_with_settings::<()>(&mut route, MatcherSettings::default());
+        assert_eq!(route, "/hello%20world%2F%3F".to_string());
+    }
+
+    #[test]
+    fn string_build_route_without_encoding() {
+        let mut route = "/".to_string();
+        let settings = MatcherSettings {
+            percent_encoding: false,
+            ..Default::default()
+        };
+        "hello world"
+            .to_string()
+            .build_route_section_with_settings::<()>(&mut route, settings);
+        assert_eq!(route, "/hello world".to_string());
+        "/a b".to_string().build_route_section::<()>(&mut route);
+        assert_eq!(route, "/hello world/a b".to_string());
+    }
+
     #[test]
     fn can_get_string_from_empty_str() {
         let (s, _state) = String::from_route_part::<()>("".to_string(), Some(()));
@@ -233,4 +408,51 @@ mod test {
             Permissive::from_route_part("".to_string(), Some(()));
         assert_eq!(s, Some(Permissive(Some("".to_string()))))
     }
+
+    /// Builds `about`, and asks for its trailing slash to be redirected.
+    #[derive(Debug, Clone)]
+    struct About;
+
+    impl Switch for About {
+        fn from_route_part<STATE>(
+            part: String,
+            state: Option<STATE>,
+        ) -> (Option<Self>, Option<STATE>) {
+            (Some(About).filter(|_| part == "about"), state)
+        }
+
+        fn build_route_section<STATE>(self, route: &mut String) -> Option<STATE> {
+            route.push_str("about");
+            None
+        }
+
+        fn trailing_slash(&self) -> TrailingSlash {
+            TrailingSlash::Redirect
+        }
+    }
+
+    #[test]
+    fn wrappers_forward_trailing_slash() {
+        assert_eq!(LeadingSlash(About).trailing_slash(), TrailingSlash::Redirect);
+        assert_eq!(Permissive(Some(About)).trailing_slash(), TrailingSlash::Redirect);
+        assert_eq!(Permissive::<About>(None).trailing_slash(), TrailingSlash::Strict);
+        assert_eq!(AllowMissing(Some(About)).trailing_slash(), TrailingSlash::Redirect);
+        assert_eq!(AllowMissing::<About>(None).trailing_slash(), TrailingSlash::Strict);
+    }
+
+    #[test]
+    fn trailing_slash_is_redirected_for_wrapped_values() {
+        assert_eq!(
+            trailing_slash_redirect(&LeadingSlash(About), "/about/?q=1"),
+            Some("/about?q=1".to_string())
+        );
+        assert_eq!(trailing_slash_redirect(&LeadingSlash(About), "/about"), None);
+        assert_eq!(trailing_slash_redirect(&About, "about/"), Some("about".to_string()));
+        assert_eq!(trailing_slash_redirect(&About, "ABOUT/"), Some("about".to_string()));
+    }
+
+    #[test]
+    fn trailing_slash_is_kept_for_strict_values() {
+        assert_eq!(trailing_slash_redirect(&LeadingSlash(5), "/5/"), None);
+    }
 }
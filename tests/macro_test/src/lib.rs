@@ -164,6 +164,8 @@ mod tests {
         pub enum Test {
             #[to = "/variant"]
             Variant1,
+            // This is knowingly shadowed by the first variant.
+            #[allow(deprecated)]
             #[to = "/variant/stuff"]
             Variant2,
         }
@@ -176,6 +178,295 @@ mod tests {
         )
     }
 
+    #[test]
+    fn variants_ordered_by_specificity() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[switch(order = "specificity")]
+        pub enum Test {
+            #[to = "/{*:rest}"]
+            CatchAll { rest: String },
+            #[to = "/!"]
+            Root,
+            #[to = "/user/{id}"]
+            User { id: String },
+            #[to = "/user/new"]
+            NewUser,
+            #[to = "/user/{id}/edit"]
+            EditUser { id: String },
+        }
+        let switch = |route: &str| Test::switch(Route::new_no_state(route));
+        assert_eq!(switch("/user/new"), Some(Test::NewUser));
+        assert_eq!(
+            switch("/user/42/edit"),
+            Some(Test::EditUser {
+                id: "42".to_string()
+            })
+        );
+        assert_eq!(
+            switch("/user/42"),
+            Some(Test::User {
+                id: "42".to_string()
+            })
+        );
+        assert_eq!(switch("/"), Some(Test::Root));
+        assert_eq!(
+            switch("/about"),
+            Some(Test::CatchAll {
+                rest: "about".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn case_sensitive_enum() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[switch(case_sensitive)]
+        pub enum Test {
+            #[to = "/About!"]
+            About,
+            #[to = "/about!"]
+            LowercaseAbout,
+            #[to = "/Contact!"]
+            #[switch(case_insensitive)]
+            Contact,
+        }
+        let switch = |route: &str| Test::switch(Route::new_no_state(route));
+        assert_eq!(switch("/About"), Some(Test::About));
+        assert_eq!(switch("/about"), Some(Test::LowercaseAbout));
+        assert_eq!(switch("/ABOUT"), None);
+        assert_eq!(switch("/contact"), Some(Test::Contact));
+        let route: Route<()> = Test::Contact.into();
+        assert_eq!(route.route, "/Contact");
+    }
+
+    #[test]
+    fn case_sensitive_variant() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/Users/{id}"]
+            #[switch(case_sensitive)]
+            Users { id: String },
+            #[to = "/about"]
+            About,
+        }
+        let switch = |route: &str| Test::switch(Route::new_no_state(route));
+        assert_eq!(
+            switch("/Users/Ann"),
+            Some(Test::Users {
+                id: "Ann".to_string()
+            })
+        );
+        assert_eq!(switch("/users/Ann"), None);
+        assert_eq!(switch("/ABOUT"), Some(Test::About));
+    }
+
+    #[test]
+    fn case_sensitive_struct() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[to = "/Search/{term}"]
+        #[switch(case_sensitive)]
+        pub struct Search {
+            term: String,
+        }
+        let search = Search {
+            term: "Rust".to_string(),
+        };
+        assert_eq!(
+            Search::switch(Route::new_no_state("/Search/Rust")),
+            Some(search.clone())
+        );
+        assert_eq!(Search::switch(Route::new_no_state("/search/Rust")), None);
+        let route: Route<()> = search.into();
+        assert_eq!(route.route, "/Search/Rust");
+    }
+
+    #[test]
+    fn trailing_slash_policies() {
+        use yew_router::matcher::{canonical_trailing_slash, TrailingSlash};
+
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[switch(trailing_slash = "ignore")]
+        pub enum Test {
+            #[to = "/about!"]
+            About,
+            #[to = "/users/!"]
+            #[switch(trailing_slash = "redirect")]
+            Users,
+            #[to = "/contact!"]
+            #[switch(trailing_slash = "strict")]
+            Contact,
+        }
+        let switch = |route: &str| Test::switch(Route::new_no_state(route));
+        assert_eq!(switch("/about"), Some(Test::About));
+        assert_eq!(switch("/about/"), Some(Test::About));
+        assert_eq!(switch("/users"), Some(Test::Users));
+        assert_eq!(switch("/users/"), Some(Test::Users));
+        assert_eq!(switch("/contact"), Some(Test::Contact));
+        assert_eq!(switch("/contact/"), None);
+
+        assert_eq!(Test::About.trailing_slash(), TrailingSlash::Ignore);
+        assert_eq!(Test::Users.trailing_slash(), TrailingSlash::Redirect);
+        assert_eq!(Test::Contact.trailing_slash(), TrailingSlash::Strict);
+        let route: Route<()> = Test::Users.into();
+        assert_eq!(route.route, "/users/");
+
+        assert_eq!(switch("/USERS"), Some(Test::Users));
+        assert_eq!(
+            canonical_trailing_slash("/USERS", &route.route),
+            Some("/users/".to_string())
+        );
+    }
+
+    #[test]
+    fn static_routes_are_unit_variants() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/users/{id}"]
+            User(u32),
+            #[to = "/about"]
+            About,
+            #[to = "/!"]
+            Home,
+        }
+        assert_eq!(Test::static_routes(), vec![Test::About, Test::Home]);
+
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[to = "/unit"]
+        pub struct Unit;
+        assert_eq!(Unit::static_routes(), vec![Unit]);
+
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[to = "/{id}"]
+        pub struct Captured(u32);
+        assert_eq!(Captured::static_routes(), vec![]);
+    }
+
+    #[test]
+    fn route_info_describes_variants() {
+        use yew_router::matcher::MatcherToken;
+
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/users/{id}/posts/{post}"]
+            Post { id: u32, post: String },
+            #[to = "/files"]
+            #[rest]
+            Files(String),
+            #[to = "/about"]
+            About,
+        }
+        let info = Test::route_info();
+        assert_eq!(info.len(), 3);
+
+        assert_eq!(info[0].ident, "Post");
+        assert_eq!(info[0].matcher_string, "/users/{id}/posts/{post}");
+        assert_eq!(info[0].field_types, vec!["u32", "String"]);
+        let mut names: Vec<&str> = info[0].capture_names().into_iter().collect();
+        names.sort();
+        assert_eq!(names, vec!["id", "post"]);
+
+        assert_eq!(info[1].ident, "Files");
+        assert_eq!(info[1].matcher_string, "/files{*}");
+        assert_eq!(info[1].field_types, vec!["String"]);
+
+        assert_eq!(info[2].ident, "About");
+        assert_eq!(info[2].tokens(), &[MatcherToken::Exact("/about".to_string())][..]);
+        assert!(info[2].field_types.is_empty());
+    }
+
+    #[test]
+    fn route_macro_encodes_values() {
+        let name = "a b/c".to_string();
+        let route: Route<()> =
+            yew_router::route!("/users/{name}/posts/{id}!", name = name, id = 5);
+        assert_eq!(route.route, "/users/a%20b%2Fc/posts/5");
+    }
+
+    #[test]
+    fn route_macro_without_captures() {
+        assert_eq!(yew_router::route!("/about").route, "/about");
+        assert_eq!(yew_router::route!("/(about|info)",).route, "/about");
+    }
+
+    #[test]
+    fn route_macro_optional_parts() {
+        let page: Option<u32> = None;
+        let route = yew_router::route!(
+            "/posts[/page/{page}]?q={q}&sort={sort?}",
+            page = page,
+            q = "x".to_string(),
+            sort = Some("new".to_string()),
+        );
+        assert_eq!(route.route, "/posts?q=x&sort=new");
+        let tags = vec!['a', 'b'];
+        let route = yew_router::route!("/posts[/page/{page}]?tag={*:tags}", page = Some(2), tags = tags);
+        assert_eq!(route.route, "/posts/page/2?tag=a&tag=b");
+    }
+
+    #[test]
+    fn route_macro_captures_can_be_named_like_its_bindings() {
+        let route = yew_router::route!("/{state}/{buf}/{__route}", state = 1, buf = 2, __route = 3);
+        assert_eq!(route.route, "/1/2/3");
+    }
+
+    #[test]
+    fn route_macro_accepts_display_values() {
+        let name = "a b";
+        let tags = ["x", "y"];
+        let route = yew_router::route!(
+            "/users/{name}[/page/{page}]?q={q}&sort={sort?}&tag={*:tags}",
+            name = name,
+            page = Some(2u8),
+            q = 'c',
+            sort = Some("new"),
+            tags = &tags,
+        );
+        assert_eq!(route.route, "/users/a%20b/page/2?q=c&sort=new&tag=x&tag=y");
+    }
+
+    #[test]
+    fn route_macro_rest_is_not_encoded() {
+        let rest = "/inner/route?x=1".to_string();
+        let route = yew_router::route!("/outer{*:rest}", rest = rest);
+        assert_eq!(route.route, "/outer/inner/route?x=1");
+    }
+
+    #[test]
+    fn route_macro_builds_same_route_as_switch() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[to = "/users/{id}?q={query}"]
+        pub struct User {
+            id: u32,
+            query: String,
+        }
+        let route: Route<()> = User {
+            id: 3,
+            query: "é?".to_string(),
+        }
+        .into();
+        assert_eq!(
+            yew_router::route!("/users/{id}?q={query}", query = "é?".to_string(), id = 3),
+            route
+        );
+    }
+
+    #[test]
+    fn equally_specific_variants_keep_declared_order() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[switch(order = "specificity")]
+        pub enum Test {
+            #[to = "/{name}"]
+            Name(String),
+            // Still shadowed, because it is only as specific as the first variant.
+            #[allow(deprecated)]
+            #[to = "/{id}"]
+            Id(u32),
+        }
+        let route = Route::new_no_state("/42");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(switched, Test::Name("42".to_string()))
+    }
+
     #[test]
     fn single_enum_variant_convert_usize() {
         #[derive(Debug, Switch, PartialEq, Clone)]
@@ -211,6 +502,279 @@ mod tests {
         assert_eq!(switched, Test::Variant(-42))
     }
 
+    #[test]
+    fn constrained_capture_falls_through_to_next_variant() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/user/{id:u32}"]
+            ById { id: String },
+            #[to = "/user/{slug:[a-z-]+}"]
+            BySlug { slug: String },
+            #[to = "/user/{}"]
+            Other(String),
+        }
+        let route = Route::new_no_state("/user/42");
+        assert_eq!(
+            Test::switch(route),
+            Some(Test::ById {
+                id: "42".to_string()
+            })
+        );
+        let route = Route::new_no_state("/user/lorem-ipsum");
+        assert_eq!(
+            Test::switch(route),
+            Some(Test::BySlug {
+                slug: "lorem-ipsum".to_string()
+            })
+        );
+        let route = Route::new_no_state("/user/Lorem");
+        assert_eq!(Test::switch(route), Some(Test::Other("Lorem".to_string())));
+    }
+
+    #[test]
+    fn query_params_in_any_order() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/search?q={query}&page={page}"]
+            Search { query: String, page: usize },
+        }
+        let route = Route::new_no_state("/search?page=2&sort=asc&q=lorem");
+        assert_eq!(
+            Test::switch(route),
+            Some(Test::Search {
+                query: "lorem".to_string(),
+                page: 2
+            })
+        );
+
+        let route: Route<()> = Test::Search {
+            query: "lorem".to_string(),
+            page: 2,
+        }
+        .into();
+        assert_eq!(route.route, "/search?q=lorem&page=2");
+    }
+
+    #[test]
+    fn optional_query_params() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/search?page={page?}&q={query?}"]
+            Search {
+                #[default]
+                page: usize,
+                query: Option<String>,
+            },
+        }
+        let route = Route::new_no_state("/search");
+        assert_eq!(
+            Test::switch(route),
+            Some(Test::Search {
+                page: 0,
+                query: None
+            })
+        );
+        let route = Route::new_no_state("/search?q=lorem&page=2");
+        assert_eq!(
+            Test::switch(route),
+            Some(Test::Search {
+                page: 2,
+                query: Some("lorem".to_string())
+            })
+        );
+
+        let route: Route<()> = Test::Search {
+            page: 0,
+            query: None,
+        }
+        .into();
+        assert_eq!(route.route, "/search?page=0");
+        let route: Route<()> = Test::Search {
+            page: 2,
+            query: Some("lorem".to_string()),
+        }
+        .into();
+        assert_eq!(route.route, "/search?page=2&q=lorem");
+    }
+
+    #[test]
+    fn optional_query_params_unnamed() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[to = "/search?q={?}&page={?}"]
+        pub struct Search(Option<String>, #[default] usize);
+
+        let route = Route::new_no_state("/search?page=3");
+        assert_eq!(Search::switch(route), Some(Search(None, 3)));
+
+        let route: Route<()> = Search(None, 3).into();
+        assert_eq!(route.route, "/search?page=3");
+    }
+
+    #[test]
+    fn repeated_query_params() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/filter?tag={*:tags}&page={page}"]
+            Filter { tags: Vec<String>, page: usize },
+        }
+        let route = Route::new_no_state("/filter?tag=a&page=2&tag=b&tag=c");
+        assert_eq!(
+            Test::switch(route),
+            Some(Test::Filter {
+                tags: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                page: 2
+            })
+        );
+        let route = Route::new_no_state("/filter?page=2");
+        assert_eq!(
+            Test::switch(route),
+            Some(Test::Filter {
+                tags: vec![],
+                page: 2
+            })
+        );
+
+        let route: Route<()> = Test::Filter {
+            tags: vec!["a".to_string(), "b".to_string()],
+            page: 2,
+        }
+        .into();
+        assert_eq!(route.route, "/filter?tag=a&tag=b&page=2");
+        let route: Route<()> = Test::Filter {
+            tags: vec![],
+            page: 2,
+        }
+        .into();
+        assert_eq!(route.route, "/filter?page=2");
+    }
+
+    #[test]
+    fn repeated_query_params_keep_empty_values() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/t?tag={*:tags}"]
+            T(Vec<String>),
+        }
+        let round_trip = |tags: Vec<&str>, route_string: &str| {
+            let tags: Vec<String> = tags.into_iter().map(String::from).collect();
+            let route: Route<()> = Test::T(tags.clone()).into();
+            assert_eq!(route.route, route_string);
+            assert_eq!(Test::switch(route), Some(Test::T(tags)));
+        };
+        round_trip(vec![], "/t");
+        round_trip(vec![""], "/t?tag=");
+        round_trip(vec!["", "a"], "/t?tag=&tag=a");
+        round_trip(vec!["a", ""], "/t?tag=a&tag=");
+    }
+
+    #[test]
+    fn repeated_query_params_unnamed() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[to = "/ids?id={*}"]
+        pub struct Ids(Vec<usize>);
+
+        let route = Route::new_no_state("/ids?id=1&id=2");
+        assert_eq!(Ids::switch(route), Some(Ids(vec![1, 2])));
+        let route = Route::new_no_state("/ids?id=1&id=two");
+        assert_eq!(Ids::switch(route), None);
+
+        let route: Route<()> = Ids(vec![1, 2]).into();
+        assert_eq!(route.route, "/ids?id=1&id=2");
+    }
+
+    #[test]
+    fn percent_encoding_round_trip() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/search/{term}?q={query}&tag={*:tags}"]
+            Search {
+                term: String,
+                query: String,
+                tags: Vec<String>,
+            },
+        }
+        let search = Test::Search {
+            term: "hello world/?".to_string(),
+            query: "a&b=c#d".to_string(),
+            tags: vec!["x&y".to_string(), "100%".to_string()],
+        };
+        let route: Route<()> = search.clone().into();
+        assert_eq!(
+            route.route,
+            "/search/hello%20world%2F%3F?q=a%26b%3Dc%23d&tag=x%26y&tag=100%25"
+        );
+        assert_eq!(Test::switch(route), Some(search));
+    }
+
+    #[test]
+    fn one_of() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/(users|members)/{id}"]
+            User { id: usize },
+        }
+        let route = Route::new_no_state("/users/42");
+        assert_eq!(Test::switch(route), Some(Test::User { id: 42 }));
+        let route = Route::new_no_state("/members/42");
+        assert_eq!(Test::switch(route), Some(Test::User { id: 42 }));
+        let route = Route::new_no_state("/admins/42");
+        assert_eq!(Test::switch(route), None);
+
+        let route: Route<()> = Test::User { id: 42 }.into();
+        assert_eq!(route.route, "/users/42");
+    }
+
+    #[test]
+    fn overlapping_alternatives() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/(a|ab)b!"]
+            B,
+        }
+        assert_eq!(Test::switch(Route::new_no_state("/ab")), Some(Test::B));
+        assert_eq!(Test::switch(Route::new_no_state("/abb")), Some(Test::B));
+        assert_eq!(Test::switch(Route::new_no_state("/abbb")), None);
+    }
+
+    #[test]
+    fn optional_group_named() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/posts[/page/{page}]!"]
+            Posts { page: Option<usize> },
+        }
+        let route = Route::new_no_state("/posts");
+        assert_eq!(Test::switch(route), Some(Test::Posts { page: None }));
+        let route = Route::new_no_state("/posts/page/3");
+        assert_eq!(Test::switch(route), Some(Test::Posts { page: Some(3) }));
+        let route = Route::new_no_state("/posts/page/lorem");
+        assert_eq!(Test::switch(route), None);
+
+        let route: Route<()> = Test::Posts { page: None }.into();
+        assert_eq!(route.route, "/posts");
+        let route: Route<()> = Test::Posts { page: Some(3) }.into();
+        assert_eq!(route.route, "/posts/page/3");
+    }
+
+    #[test]
+    fn optional_group_unnamed() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[to = "/user/{}[/{}]/edit"]
+        pub struct Test(String, Option<String>);
+        let route = Route::new_no_state("/user/lorem/edit");
+        assert_eq!(Test::switch(route), Some(Test("lorem".to_string(), None)));
+        let route = Route::new_no_state("/user/lorem/ipsum/edit");
+        assert_eq!(
+            Test::switch(route),
+            Some(Test("lorem".to_string(), Some("ipsum".to_string())))
+        );
+
+        let route: Route<()> = Test("lorem".to_string(), None).into();
+        assert_eq!(route.route, "/user/lorem/edit");
+        let route: Route<()> = Test("lorem".to_string(), Some("ipsum".to_string())).into();
+        assert_eq!(route.route, "/user/lorem/ipsum/edit");
+    }
+
     #[test]
     fn single_enum_variant_missing_cap_produces_permissive_option_none() {
         #[derive(Debug, Switch, PartialEq, Clone)]
@@ -487,4 +1051,150 @@ mod tests {
             }
         }
     }
+
+    /// Tests of the helpers that work from the routes of a derived `Switch`.
+    mod app_route_tests {
+        use super::*;
+        use yew_router::{
+            manifest::{self, ManifestRoute, RouteManifest},
+            server::{ClientRoutes, Resolution},
+            sitemap,
+        };
+
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum AppRoute {
+            #[to = "/users/{id}/posts/{post}"]
+            Post { id: u32, post: u32 },
+            #[to = "/users/{id}"]
+            User(u32),
+            #[to = "/search?q={query}&sort=new"]
+            Search(String),
+            #[to = "/about"]
+            About,
+            #[to = "/!"]
+            Home,
+        }
+
+        fn client_routes() -> ClientRoutes<AppRoute> {
+            ClientRoutes::new().exclude("/api/")
+        }
+
+        #[test]
+        fn known_routes_are_found() {
+            assert_eq!(client_routes().resolve("/"), Resolution::Found);
+            assert_eq!(client_routes().resolve("/users/5"), Resolution::Found);
+            assert_eq!(client_routes().resolve("/search?sort=new&q=x"), Resolution::Found);
+            assert_eq!(client_routes().resolve("/users/five"), Resolution::NotFound);
+        }
+
+        #[test]
+        fn excluded_prefix_matches_whole_segments() {
+            assert_eq!(client_routes().resolve("/api"), Resolution::Excluded);
+            assert_eq!(client_routes().resolve("/api/users/5?q=1"), Resolution::Excluded);
+            assert_eq!(client_routes().resolve("/apiary"), Resolution::NotFound);
+        }
+
+        #[test]
+        fn base_path_is_stripped() {
+            let routes = client_routes().base_path("/app");
+            assert_eq!(routes.resolve("/app/users/5"), Resolution::Found);
+            assert_eq!(routes.resolve("/app"), Resolution::Found);
+            assert_eq!(routes.resolve("/users/5"), Resolution::NotFound);
+        }
+
+        #[test]
+        fn manifest_describes_routes() {
+            let manifest = RouteManifest::of::<AppRoute>();
+            assert_eq!(manifest.routes.len(), 5);
+            assert_eq!(
+                manifest.routes[0],
+                ManifestRoute {
+                    variant: "Post".to_string(),
+                    pattern: "/users/{id}/posts/{post}".to_string(),
+                    captures: vec!["id".to_string(), "post".to_string()],
+                    exact: false,
+                    field_types: vec!["u32".to_string(), "u32".to_string()],
+                }
+            );
+            assert_eq!(
+                manifest.routes[4],
+                ManifestRoute {
+                    variant: "Home".to_string(),
+                    pattern: "/!".to_string(),
+                    captures: vec![],
+                    exact: true,
+                    field_types: vec![],
+                }
+            );
+        }
+
+        #[test]
+        fn manifest_routes_are_exact_before_a_query_or_after_a_group() {
+            #[derive(Debug, Switch, PartialEq, Clone)]
+            pub enum ExactRoute {
+                #[to = "/search?q={query}!"]
+                Search(String),
+                #[to = "/users[/{id}]!"]
+                Users(Option<u32>),
+                #[to = "/posts[/{id}]"]
+                Posts(Option<u32>),
+            }
+            let exact: Vec<bool> = RouteManifest::of::<ExactRoute>()
+                .routes
+                .iter()
+                .map(|route| route.exact)
+                .collect();
+            assert_eq!(exact, vec![true, true, false]);
+        }
+
+        #[test]
+        fn manifest_json_round_trips() {
+            let manifest = RouteManifest::of::<AppRoute>();
+            let json = manifest.to_json();
+            assert!(json.contains("\"pattern\": \"/users/{id}/posts/{post}\""));
+            assert_eq!(RouteManifest::from_json(&json).unwrap(), manifest);
+        }
+
+        #[test]
+        fn manifest_is_written() {
+            let dir = std::env::temp_dir()
+                .join(format!("yew_router_manifest_{}", std::process::id()));
+            let path = dir.join("nested").join("routes.json");
+            manifest::write_manifest::<AppRoute, _>(&path).unwrap();
+            assert_eq!(
+                std::fs::read_to_string(&path).unwrap(),
+                RouteManifest::of::<AppRoute>().to_json()
+            );
+            manifest::write_manifest::<AppRoute, _>(&path).unwrap();
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn sitemap_lists_static_routes() {
+            assert_eq!(
+                sitemap::sitemap::<AppRoute>("https://example.com/"),
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n  \
+                 <url><loc>https://example.com/about</loc></url>\n  \
+                 <url><loc>https://example.com/</loc></url>\n\
+                 </urlset>\n"
+            );
+        }
+
+        #[test]
+        fn sitemap_escapes_provided_routes() {
+            let provider = || vec![AppRoute::Search("x".to_string())];
+            let xml = sitemap::sitemap_with::<AppRoute, _>("https://example.com", &provider);
+            assert!(xml.contains("<loc>https://example.com/search?q=x&amp;sort=new</loc>"));
+        }
+
+        #[test]
+        fn sitemap_removes_duplicate_routes() {
+            let provider = || vec![AppRoute::Home, AppRoute::User(5), AppRoute::User(5)];
+            assert_eq!(
+                sitemap::enumerate_routes::<AppRoute, _>(&provider),
+                vec!["/about".to_string(), "/".to_string(), "/users/5".to_string()]
+            );
+        }
+    }
 }